@@ -1,23 +1,57 @@
-use std::fmt::format;
-
 use cosmwasm_std::{
-    entry_point, to_binary, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Response,
-    StdError, StdResult, Uint128,
+    entry_point, from_binary, to_binary, Addr, Api, BankMsg, Binary, Coin, Decimal, Deps, DepsMut,
+    Empty, Env, MessageInfo, Order, Response, StdError, StdResult, Storage, Timestamp, Uint128,
+    WasmMsg,
 };
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw721_base::state::TokenInfo;
 pub use cw721_base::{
     ContractError as BaseContractError, InstantiateMsg as BaseInstantiateMsg, MinterResponse,
 };
-use cw_utils::must_pay;
+use cw_storage_plus::Bound;
+use cw_utils::{must_pay, one_coin, Duration, Expiration, PaymentError};
 
 use crate::{
-    msg::{Cw721Contract, ExecuteExt, ExecuteMsg, InstantiateMsg, MetadataExt, QueryExt, QueryMsg},
+    msg::{
+        BaseExecuteMsg, BeneficiaryMsg, BeneficiaryResponse, BreakRequestResponse,
+        BreakRequestsResponse, ConfigResponse, Contribution, ContributionsResponse, Cw721Contract,
+        DenomSolvency, DepositLimitsResponse, ExecuteExt, ExecuteMsg, FeeMsg, GuardianResponse,
+        InstantiateMsg, LockResponse, MetadataExt, PaymentOptionMsg, PaymentOptionsResponse,
+        PlanResponse, PlansResponse, QueryExt, QueryMsg, RaiseResponse, RaiseStatus, ReceiveMsg,
+        RecoveryMsg, RecoveryResponse, RecoveryVote, RelayersResponse, RolesResponse,
+        SimulateBurnResponse, SimulateDepositResponse, SolvencyResponse,
+    },
     state::{
-        BALANCES, BASE_URL, DEPOSIT_DENOM, MAX_NFT_SUPPLY, MINT_PRICE, PREVIOUS_TOKEN_ID,
-        SALE_FUNDS_RECIPIENT,
+        AuctionProgress, AuctionPurchase, Beneficiary, BondingCurve, BreakRequest, DutchAuction,
+        FeeConfig, Pause, PaymentOption, PendingRecovery, Plan, RecoveryConfig, Role,
+        ADMIN_RECOVERY, AUCTION, AUCTION_PROGRESS, AUCTION_PURCHASES, BALANCES, BASE_URL,
+        BENEFICIARIES, BONDING_CURVE, BREAK_REQUESTS, CONTRIBUTIONS, DEPOSIT_DENOM,
+        EARLY_BREAK_PENALTY, ESCROWED_PROCEEDS, FEE_CONFIG, GUARDIANS, GUARDIAN_TIMEOUT,
+        LAST_ACTIVITY, LAST_WITHDRAWALS, LOCKS, LOCK_STARTS, MAX_MINTS_PER_WALLET, MAX_NFT_SUPPLY,
+        MAX_SPLITS, MAX_TOKEN_BALANCE, MINT_PAYMENTS, MINT_PRICE, MINT_SEED, MIN_DEPOSIT,
+        MIN_RAISE, OPERATOR_PAYOUT_OPT_INS, PAUSE, PAYMENT_OPTIONS, PENALTY_INDEX,
+        PENDING_RECOVERIES, PLANS, PLANS_DUE_AT_HEIGHT, PLANS_DUE_AT_TIME, PREVIOUS_PLAN_ID,
+        PREVIOUS_TOKEN_ID, RAISE_MINTED, RECOVERY_CONFIGS, RECOVERY_VOTES, RELAYERS,
+        REQUIRE_EXPECTED_BALANCE, ROLES, SALE_FUNDS_RECIPIENT, SOULBOUND, SPLITS, TOKENS_SOLD,
+        TOKEN_MIN_DEPOSITS, TOKEN_PENALTY_INDEX, TOKEN_PLANS, TOTAL_DEPOSITS, TRANSFER_COOLDOWN,
+        WALLET_MINTS,
     },
     ContractError,
 };
 
+// Pagination limits for queries and the number of plans executed by a crank
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+// Basis points in a whole, used for fees
+const MAX_BPS: u64 = 10_000;
+
+// Highest withdrawal fee, so the fee can't be raised to take a balance
+const MAX_FEE_BPS: u64 = 1_000;
+
+// Longest time withdrawals can be paused for, and how long before they can be paused again
+const MAX_WITHDRAW_PAUSE: u64 = 7 * 24 * 60 * 60;
+
 // Version info for migration
 pub const CONTRACT_NAME: &str = "crates.io:cw721-piggy-bank";
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -29,38 +63,182 @@ pub fn instantiate(
     env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     // TODO Validate denoms are formated correctly
 
     // Save config info
     DEPOSIT_DENOM.save(deps.storage, &msg.deposit_denom)?;
+    TOTAL_DEPOSITS.save(deps.storage, &msg.deposit_denom, &Uint128::zero())?;
     // TODO validate base_url is a real url
     BASE_URL.save(deps.storage, &msg.base_url)?;
     MINT_PRICE.save(deps.storage, &msg.mint_price)?;
     if let Some(max_nft_supply) = msg.max_nft_supply {
         MAX_NFT_SUPPLY.save(deps.storage, &max_nft_supply)?;
     }
+    if let Some(max_splits) = msg.max_splits {
+        MAX_SPLITS.save(deps.storage, &max_splits)?;
+    }
     SALE_FUNDS_RECIPIENT.save(
         deps.storage,
         &deps.api.addr_validate(&msg.sale_funds_recipient)?,
     )?;
 
-    // Set initial previous token id to zero
+    // Save deposit limits, the minimum can't be above the maximum
+    if let (Some(min_deposit), Some(max_token_balance)) = (msg.min_deposit, msg.max_token_balance) {
+        if min_deposit > max_token_balance {
+            return Err(ContractError::InvalidDepositLimits {});
+        }
+    }
+    if let Some(min_deposit) = msg.min_deposit {
+        MIN_DEPOSIT.save(deps.storage, &min_deposit)?;
+    }
+    if let Some(max_token_balance) = msg.max_token_balance {
+        MAX_TOKEN_BALANCE.save(deps.storage, &max_token_balance)?;
+    }
+    if let Some(early_break_penalty_bps) = msg.early_break_penalty_bps {
+        if early_break_penalty_bps > MAX_BPS {
+            return Err(ContractError::InvalidFee {});
+        }
+        EARLY_BREAK_PENALTY.save(deps.storage, &early_break_penalty_bps)?;
+    }
+    if let Some(fee) = msg.fee {
+        FEE_CONFIG.save(deps.storage, &validate_fee(deps.api, fee)?)?;
+    }
+
+    if let Some(guardian_timeout) = msg.guardian_timeout {
+        GUARDIAN_TIMEOUT.save(deps.storage, &guardian_timeout)?;
+    }
+
+    // Save the crowdfunding goal, escrowed proceeds count towards what is owed
+    if let Some(min_raise) = msg.min_raise {
+        MIN_RAISE.save(deps.storage, &min_raise)?;
+        RAISE_MINTED.save(deps.storage, &0)?;
+        ESCROWED_PROCEEDS.save(deps.storage, &Uint128::zero())?;
+        increase_total_deposits(deps.storage, &msg.mint_price.denom, Uint128::zero())?;
+    }
+    if let Some(auction) = msg.dutch_auction {
+        if auction.floor_price > auction.start_price
+            || auction.decay_interval == Duration::Height(0)
+            || auction.decay_interval == Duration::Time(0)
+            || (auction.rebate && MIN_RAISE.may_load(deps.storage)?.is_some())
+        {
+            return Err(ContractError::InvalidAuction {});
+        }
+        AUCTION.save(
+            deps.storage,
+            &DutchAuction {
+                start_price: auction.start_price,
+                floor_price: auction.floor_price,
+                decay: auction.decay,
+                decay_interval: auction.decay_interval,
+                rebate: auction.rebate,
+                start_height: env.block.height,
+                start_time: env.block.time,
+            },
+        )?;
+        AUCTION_PROGRESS.save(deps.storage, &AuctionProgress::default())?;
+        increase_total_deposits(deps.storage, &msg.mint_price.denom, Uint128::zero())?;
+    }
+    if let Some(bonding_curve) = msg.bonding_curve {
+        if AUCTION.may_load(deps.storage)?.is_some() {
+            return Err(ContractError::InvalidBondingCurve {});
+        }
+        BONDING_CURVE.save(deps.storage, &bonding_curve)?;
+    }
+
+    // Seeds aren't refundable, so they can't be combined with a minimum raise
+    if let Some(mint_seed_bps) = msg.mint_seed_bps {
+        if mint_seed_bps > MAX_BPS
+            || msg.mint_price.denom != msg.deposit_denom
+            || MIN_RAISE.may_load(deps.storage)?.is_some()
+        {
+            return Err(ContractError::InvalidMintSeed {});
+        }
+        MINT_SEED.save(deps.storage, &mint_seed_bps)?;
+    }
+    if let Some(max_mints_per_wallet) = msg.max_mints_per_wallet {
+        MAX_MINTS_PER_WALLET.save(deps.storage, &max_mints_per_wallet)?;
+    }
+
+    // Save the other ways to pay for a mint, these are sold at a fixed price
+    // and can't seed the NFT
+    if !msg.payment_options.is_empty()
+        && (MIN_RAISE.may_load(deps.storage)?.is_some()
+            || AUCTION.may_load(deps.storage)?.is_some()
+            || BONDING_CURVE.may_load(deps.storage)?.is_some()
+            || MINT_SEED.may_load(deps.storage)?.is_some())
+    {
+        return Err(ContractError::InvalidPaymentOptions {});
+    }
+    let mut payment_options: Vec<PaymentOption> = vec![];
+    for option in msg.payment_options {
+        let option = match option {
+            PaymentOptionMsg::Native { denom, amount } => PaymentOption::Native { denom, amount },
+            PaymentOptionMsg::Cw20 { address, amount } => PaymentOption::Cw20 {
+                address: deps.api.addr_validate(&address)?,
+                amount,
+            },
+        };
+        let duplicate = payment_options
+            .iter()
+            .any(|existing| match (existing, &option) {
+                (
+                    PaymentOption::Native { denom: a, .. },
+                    PaymentOption::Native { denom: b, .. },
+                ) => a == b,
+                (
+                    PaymentOption::Cw20 { address: a, .. },
+                    PaymentOption::Cw20 { address: b, .. },
+                ) => a == b,
+                _ => false,
+            });
+        let invalid = match &option {
+            PaymentOption::Native { denom, amount } => {
+                *denom == msg.mint_price.denom || amount.is_zero()
+            }
+            PaymentOption::Cw20 { amount, .. } => amount.is_zero(),
+        };
+        if duplicate || invalid {
+            return Err(ContractError::InvalidPaymentOptions {});
+        }
+        payment_options.push(option);
+    }
+    PAYMENT_OPTIONS.save(deps.storage, &payment_options)?;
+
+    // Save transfer protections, admin recovery only makes sense for soulbound NFTs
+    if msg.allow_admin_recovery && !msg.soulbound {
+        return Err(ContractError::InvalidAdminRecovery {});
+    }
+    SOULBOUND.save(deps.storage, &msg.soulbound)?;
+    ADMIN_RECOVERY.save(deps.storage, &msg.allow_admin_recovery)?;
+    REQUIRE_EXPECTED_BALANCE.save(deps.storage, &msg.require_expected_balance)?;
+    if let Some(transfer_cooldown) = msg.transfer_cooldown {
+        TRANSFER_COOLDOWN.save(deps.storage, &transfer_cooldown)?;
+    }
+
+    PAUSE.save(deps.storage, &Pause::default())?;
+
+    // Set initial previous token and plan ids, and the sale and split counts, to zero
     PREVIOUS_TOKEN_ID.save(deps.storage, &0)?;
+    PREVIOUS_PLAN_ID.save(deps.storage, &0)?;
+    TOKENS_SOLD.save(deps.storage, &0)?;
+    SPLITS.save(deps.storage, &0)?;
 
     // Instantiate the base contract
-    Cw721Contract::default().instantiate(
-        deps.branch(),
-        env,
-        info,
-        BaseInstantiateMsg {
-            minter: msg.minter,
-            name: msg.name,
-            symbol: msg.symbol,
-        },
-    )
+    Cw721Contract::default()
+        .instantiate(
+            deps.branch(),
+            env,
+            info,
+            BaseInstantiateMsg {
+                minter: msg.minter,
+                name: msg.name,
+                symbol: msg.symbol,
+            },
+        )
+        .map_err(Into::into)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -70,12 +248,39 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // Soulbound NFTs can't be transferred or approved for transfer
+    if matches!(
+        msg,
+        ExecuteMsg::TransferNft { .. }
+            | ExecuteMsg::SendNft { .. }
+            | ExecuteMsg::Approve { .. }
+            | ExecuteMsg::ApproveAll { .. }
+            | ExecuteMsg::Extension {
+                msg: ExecuteExt::TransferWithBalance { .. } | ExecuteExt::SendWithBalance { .. }
+            }
+    ) && SOULBOUND.load(deps.storage)?
+    {
+        return Err(ContractError::Soulbound {});
+    }
+    check_paused(deps.storage, &env, &msg)?;
+
     match msg {
         // Optionally override the default cw721-base behavior
         ExecuteMsg::Burn { token_id } => execute_burn(deps, env, info, token_id),
 
         // Overrides default Mint method. Used to purchase and create initial NFTs
-        ExecuteMsg::Mint { .. } => execute_mint(deps, env, info),
+        ExecuteMsg::Mint { guardian, .. } => execute_mint(deps, env, info, guardian),
+
+        // Overrides default transfers to protect piggy bank balances on sale
+        ExecuteMsg::TransferNft {
+            recipient,
+            token_id,
+        } => execute_transfer_nft(deps, env, info, recipient, token_id, None),
+        ExecuteMsg::SendNft {
+            contract,
+            token_id,
+            msg,
+        } => execute_send_nft(deps, env, info, contract, token_id, msg, None),
 
         // Implment extension messages here, remove if you don't wish to use
         // An ExecuteExt extension
@@ -85,15 +290,212 @@ pub fn execute(
                 token_id,
                 token_uri,
             } => execute_update_token_uri(deps, env, info, token_id, token_uri),
+            ExecuteExt::SetMinDeposit { token_id, amount } => {
+                execute_set_min_deposit(deps, env, info, token_id, amount)
+            }
+            ExecuteExt::CreatePlan {
+                token_id,
+                amount,
+                interval,
+                tip,
+            } => execute_create_plan(deps, env, info, token_id, amount, interval, tip),
+            ExecuteExt::CancelPlan { plan_id } => execute_cancel_plan(deps, env, info, plan_id),
+            ExecuteExt::Crank { limit } => execute_crank(deps, env, info, limit),
+            ExecuteExt::DepositFor {
+                token_id,
+                contributor,
+                beneficiary_note,
+            } => execute_deposit_for(deps, env, info, token_id, contributor, beneficiary_note),
+            ExecuteExt::UpdateRelayers { add, remove } => {
+                execute_update_relayers(deps, env, info, add, remove)
+            }
+            ExecuteExt::BreakOpen {
+                token_id,
+                recipient,
+            } => execute_break_open(deps, env, info, token_id, recipient),
+            ExecuteExt::AllowOperatorPayout { token_id, allowed } => {
+                execute_allow_operator_payout(deps, env, info, token_id, allowed)
+            }
+            ExecuteExt::Lock { token_id, until } => execute_lock(deps, env, info, token_id, until),
+            ExecuteExt::UpdateFee { fee } => execute_update_fee(deps, env, info, fee),
+            ExecuteExt::Merge { source, target } => execute_merge(deps, env, info, source, target),
+            ExecuteExt::Split {
+                token_id,
+                amount,
+                recipient,
+            } => execute_split(deps, env, info, token_id, amount, recipient),
+            ExecuteExt::TransferWithBalance {
+                recipient,
+                token_id,
+                expected_balance,
+            } => execute_transfer_nft(deps, env, info, recipient, token_id, Some(expected_balance)),
+            ExecuteExt::SendWithBalance {
+                contract,
+                token_id,
+                msg,
+                expected_balance,
+            } => execute_send_nft(
+                deps,
+                env,
+                info,
+                contract,
+                token_id,
+                msg,
+                Some(expected_balance),
+            ),
+            ExecuteExt::RecoveryTransfer {
+                token_id,
+                recipient,
+            } => execute_recovery_transfer(deps, env, info, token_id, recipient),
+            ExecuteExt::SetGuardian { token_id, guardian } => {
+                execute_set_guardian(deps, env, info, token_id, guardian)
+            }
+            ExecuteExt::RequestBreak { token_id } => {
+                execute_request_break(deps, env, info, token_id)
+            }
+            ExecuteExt::ApproveBreak { token_id } => {
+                execute_approve_break(deps, env, info, token_id)
+            }
+            ExecuteExt::SetRecovery { token_id, recovery } => {
+                execute_set_recovery(deps, env, info, token_id, recovery)
+            }
+            ExecuteExt::Recover {
+                token_id,
+                new_owner,
+            } => execute_recover(deps, env, info, token_id, new_owner),
+            ExecuteExt::CancelRecovery { token_id } => {
+                execute_cancel_recovery(deps, env, info, token_id)
+            }
+            ExecuteExt::SetBeneficiary {
+                token_id,
+                beneficiary,
+            } => execute_set_beneficiary(deps, env, info, token_id, beneficiary),
+            ExecuteExt::Ping { token_id } => execute_ping(deps, env, info, token_id),
+            ExecuteExt::ClaimInheritance { token_id } => {
+                execute_claim_inheritance(deps, env, info, token_id)
+            }
+            ExecuteExt::UpdatePause {
+                mint,
+                deposit,
+                withdraw,
+                transfer,
+            } => execute_update_pause(deps, env, info, mint, deposit, withdraw, transfer),
+            ExecuteExt::GrantRole { address, role } => {
+                execute_update_role(deps, env, info, address, role, true)
+            }
+            ExecuteExt::RevokeRole { address, role } => {
+                execute_update_role(deps, env, info, address, role, false)
+            }
+            ExecuteExt::Sweep {
+                denom,
+                amount,
+                recipient,
+            } => execute_sweep(deps, env, info, denom, amount, recipient),
+            ExecuteExt::CreditUnassigned { token_id, amount } => {
+                execute_credit_unassigned(deps, env, info, token_id, amount)
+            }
+            ExecuteExt::ReleaseProceeds {} => execute_release_proceeds(deps, env, info),
+            ExecuteExt::ClaimRebate {} => execute_claim_rebate(deps, env, info),
+            ExecuteExt::PurchaseBatch {
+                count,
+                recipient,
+                guardian,
+            } => execute_purchase_batch(deps, env, info, count, recipient, guardian),
         },
 
+        // Mints paid with cw20 tokens
+        ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
+
         // Use the default cw721-base implementation
         _ => Cw721Contract::default()
-            .execute(deps, env, info, msg)
+            .execute(deps, env, info, msg.try_into()?)
             .map_err(Into::into),
     }
 }
 
+/// Rejects messages whose group of actions is paused
+fn check_paused(storage: &dyn Storage, env: &Env, msg: &ExecuteMsg) -> Result<(), ContractError> {
+    let pause = PAUSE.load(storage)?;
+    let withdraw = matches!(pause.withdraw_until, Some(until) if env.block.time < until);
+    let paused = match msg {
+        ExecuteMsg::Mint { .. } | ExecuteMsg::Receive(_) => pause.mint,
+        ExecuteMsg::Burn { .. } => withdraw,
+        ExecuteMsg::TransferNft { .. } | ExecuteMsg::SendNft { .. } => pause.transfer,
+        ExecuteMsg::Extension { msg } => match msg {
+            ExecuteExt::Deposit { .. }
+            | ExecuteExt::DepositFor { .. }
+            | ExecuteExt::CreatePlan { .. }
+            | ExecuteExt::Crank { .. } => pause.deposit,
+            ExecuteExt::PurchaseBatch { .. } => pause.mint,
+            ExecuteExt::BreakOpen { .. }
+            | ExecuteExt::CancelPlan { .. }
+            | ExecuteExt::ClaimRebate {}
+            | ExecuteExt::ReleaseProceeds {} => withdraw,
+            ExecuteExt::Split { .. } => withdraw || pause.mint,
+            ExecuteExt::TransferWithBalance { .. }
+            | ExecuteExt::SendWithBalance { .. }
+            | ExecuteExt::RecoveryTransfer { .. }
+            | ExecuteExt::Recover { .. }
+            | ExecuteExt::ClaimInheritance { .. } => pause.transfer,
+            _ => false,
+        },
+        _ => false,
+    };
+    if paused {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
+}
+
+/// Pauses or resumes groups of actions, only callable by a pauser. Withdrawals
+/// resume on their own after MAX_WITHDRAW_PAUSE and can't be paused again until
+/// as long again has passed.
+pub fn execute_update_pause(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mint: bool,
+    deposit: bool,
+    withdraw: bool,
+    transfer: bool,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Pauser)?;
+
+    let mut pause = PAUSE.load(deps.storage)?;
+    pause.mint = mint;
+    pause.deposit = deposit;
+    pause.transfer = transfer;
+    match pause.withdraw_until {
+        // Already paused, the pause can't be extended
+        Some(until) if withdraw && env.block.time < until => {}
+        // Resuming early still counts towards the cooldown
+        Some(until) if !withdraw && env.block.time < until => {
+            pause.withdraw_until = Some(env.block.time);
+        }
+        Some(until) if withdraw => {
+            let cooldown_until = until.plus_seconds(MAX_WITHDRAW_PAUSE);
+            if env.block.time < cooldown_until {
+                return Err(ContractError::WithdrawPauseCooldown {
+                    until: cooldown_until,
+                });
+            }
+            pause.withdraw_until = Some(env.block.time.plus_seconds(MAX_WITHDRAW_PAUSE));
+        }
+        None if withdraw => {
+            pause.withdraw_until = Some(env.block.time.plus_seconds(MAX_WITHDRAW_PAUSE));
+        }
+        _ => {}
+    }
+    PAUSE.save(deps.storage, &pause)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "update_pause")
+        .add_attribute("mint", mint.to_string())
+        .add_attribute("deposit", deposit.to_string())
+        .add_attribute("withdraw", withdraw.to_string())
+        .add_attribute("transfer", transfer.to_string()))
+}
+
 pub fn execute_update_token_uri(
     deps: DepsMut,
     _env: Env,
@@ -103,18 +505,8 @@ pub fn execute_update_token_uri(
 ) -> Result<Response, ContractError> {
     let base = Cw721Contract::default();
 
-    // Check minter / admin to update token_uri
-    let minter = base.minter(deps.as_ref())?;
-    match minter.minter {
-        Some(minter) => {
-            if info.sender != minter {
-                return Err(ContractError::Unauthorized {});
-            }
-        }
-        None => {
-            return Err(ContractError::Unauthorized {});
-        }
-    }
+    // Check the sender can update metadata
+    assert_role(deps.as_ref(), &info.sender, Role::MetadataUpdater)?;
 
     // Update token_uri
     let mut token = base.tokens.load(deps.storage, &token_id)?;
@@ -132,143 +524,2466 @@ pub fn execute_burn(
     env: Env,
     info: MessageInfo,
     token_id: String,
+) -> Result<Response, ContractError> {
+    // Burning pays out to the owner, the same as breaking open without a recipient
+    execute_break_open(deps, env, info, token_id, None)
+}
+
+pub fn execute_break_open(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+    recipient: Option<String>,
 ) -> Result<Response, ContractError> {
     let base = Cw721Contract::default();
 
-    let denom = DEPOSIT_DENOM.load(deps.storage)?;
+    let token = base
+        .tokens
+        .load(deps.storage, &token_id)
+        .map_err(BaseContractError::from)?;
+
+    // Funds go to the owner unless the owner picks someone else, or has opted
+    // in to letting operators pick someone else
+    let recipient = match recipient {
+        Some(recipient) => {
+            let recipient = deps.api.addr_validate(&recipient)?;
+            let opted_in = OPERATOR_PAYOUT_OPT_INS.may_load(deps.storage, &token_id)?
+                == Some(token.owner.clone());
+            if recipient != token.owner && info.sender != token.owner && !opted_in {
+                return Err(ContractError::Unauthorized {});
+            }
+            recipient
+        }
+        None => token.owner,
+    };
+
+    // Work out the fee, penalty and mint refund before burning, this fails if the
+    // NFT is still locked and early breaks aren't allowed, or the guardian hasn't approved
+    settle_token(deps.storage, &token_id)?;
+    let payout = burn_payout(deps.storage, &env, &token_id)?;
+    if let Some(mint_refund) = &payout.mint_refund {
+        take_mint_refund(deps.storage, &token_id, mint_refund)?;
+    }
+
+    // Pass off to default cw721 burn implementation, handles checking ownership
+    base.execute(
+        deps.branch(),
+        env,
+        info,
+        BaseExecuteMsg::Burn {
+            token_id: token_id.clone(),
+        },
+    )?;
+    remove_token_state(deps.storage, &token_id)?;
+
+    // Share the penalty for breaking early between the remaining piggy banks
+    if !payout.penalty.is_zero() {
+        let remaining = base.token_count(deps.storage)?;
+        let index = PENALTY_INDEX.may_load(deps.storage)?.unwrap_or_default();
+        PENALTY_INDEX.save(
+            deps.storage,
+            &(index + Decimal::from_ratio(payout.penalty, remaining)),
+        )?;
+    }
 
-    // Pay out the piggy bank!
-    let balance = BALANCES.may_load(deps.storage, &token_id)?;
-    let msgs = match balance {
-        Some(balance) => {
-            vec![BankMsg::Send {
-                to_address: info.sender.to_string(),
+    // Pay out the piggy bank! The penalty stays in the contract for the other NFTs
+    let denom = DEPOSIT_DENOM.load(deps.storage)?;
+    decrease_total_deposits(deps.storage, &denom, payout.payout + payout.fee)?;
+    let mut res = Response::default()
+        .add_attribute("action", "break_open")
+        .add_attribute("token_id", token_id)
+        .add_attribute("recipient", recipient.to_string())
+        .add_attribute("payout", payout.payout)
+        .add_attribute("fee", payout.fee)
+        .add_attribute("penalty", payout.penalty);
+    if !payout.payout.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin {
+                denom: denom.clone(),
+                amount: payout.payout,
+            }],
+        });
+    }
+    if let Some(mint_refund) = payout.mint_refund {
+        res = res
+            .add_attribute("mint_refund", mint_refund.to_string())
+            .add_message(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![mint_refund],
+            });
+    }
+    if let Some(treasury) = payout.treasury {
+        res = res
+            .add_attribute("treasury", treasury.to_string())
+            .add_message(BankMsg::Send {
+                to_address: treasury.to_string(),
                 amount: vec![Coin {
                     denom,
-                    amount: balance,
+                    amount: payout.fee,
                 }],
-            }]
+            });
+    }
+    Ok(res)
+}
+
+/// How the balance of an NFT is split up when it is broken open
+pub struct BurnPayout {
+    /// Amount paid to the recipient
+    pub payout: Uint128,
+    /// Amount paid to the treasury
+    pub fee: Uint128,
+    /// The treasury, None if no fee is taken
+    pub treasury: Option<Addr>,
+    /// Amount forfeited to the other piggy banks for breaking a lock early
+    pub penalty: Uint128,
+    /// The escrowed mint payment refunded if the minimum raise failed
+    pub mint_refund: Option<Coin>,
+}
+
+/// Works out what breaking open an NFT pays out right now, without writing state.
+/// Fails if the NFT can't be broken open yet.
+pub fn burn_payout(
+    storage: &dyn Storage,
+    env: &Env,
+    token_id: &str,
+) -> Result<BurnPayout, ContractError> {
+    let balance = token_balance(storage, token_id)?;
+
+    // Guarded NFTs need the guardian's approval, or the request to time out
+    check_break_approved(storage, env, token_id)?;
+
+    // Locked NFTs can't be broken open until they mature, unless the collection
+    // allows breaking early for a penalty
+    let lock = LOCKS.may_load(storage, token_id)?;
+    let matured = match lock {
+        Some(until) if env.block.time < until => {
+            if EARLY_BREAK_PENALTY.may_load(storage)?.is_none() {
+                return Err(ContractError::Locked { until });
+            }
+            false
         }
-        None => vec![],
+        Some(_) => true,
+        None => false,
     };
 
-    // Pass off to default cw721 burn implementation, handles checking ownership
-    base.execute(deps, env, info, ExecuteMsg::Burn { token_id })?;
+    // The penalty is shared with the other piggy banks, so it only applies if there are any
+    let penalty = match EARLY_BREAK_PENALTY.may_load(storage)? {
+        Some(bps) if lock.is_some() && !matured => {
+            if Cw721Contract::default().token_count(storage)? > 1 {
+                balance.multiply_ratio(bps, MAX_BPS)
+            } else {
+                Uint128::zero()
+            }
+        }
+        _ => Uint128::zero(),
+    };
 
-    Ok(Response::default().add_messages(msgs))
+    // Take the withdrawal fee, unless the NFT was held locked until maturity
+    // for at least the minimum lock duration
+    let remaining = balance - penalty;
+    let fee_config = FEE_CONFIG.may_load(storage)?;
+    let locked_for = match (lock, LOCK_STARTS.may_load(storage, token_id)?) {
+        (Some(until), Some(locked_at)) => until.seconds() - locked_at.seconds(),
+        _ => 0,
+    };
+    let waived = matches!(&fee_config, Some(fee_config)
+        if fee_config.waive_after_maturity && matured && locked_for >= fee_config.min_lock_duration);
+    let (fee, treasury) = match fee_config {
+        Some(fee_config) if !waived => {
+            let fee = remaining.multiply_ratio(fee_config.bps, MAX_BPS);
+            (fee, Some(fee_config.treasury).filter(|_| !fee.is_zero()))
+        }
+        _ => (Uint128::zero(), None),
+    };
+
+    Ok(BurnPayout {
+        payout: remaining - fee,
+        fee,
+        treasury,
+        penalty,
+        mint_refund: mint_refund(storage, env, token_id)?,
+    })
 }
 
-// NOTE: in real life, add some randomness to minting, otherwise people will game it
-pub fn execute_mint(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
-    // Load mint_price and base_url
-    let base_url = BASE_URL.load(deps.storage)?;
-    let mint_price = MINT_PRICE.load(deps.storage)?;
+/// Returns the share of early-break penalties an NFT hasn't been credited yet,
+/// and the penalty index to record once it has
+fn pending_penalty_share(storage: &dyn Storage, token_id: &str) -> StdResult<(Uint128, Decimal)> {
+    let index = PENALTY_INDEX.may_load(storage)?.unwrap_or_default();
+    let token_index = TOKEN_PENALTY_INDEX
+        .may_load(storage, token_id)?
+        .unwrap_or_default();
 
-    // Check the right amount of funds were sent
-    let amount = must_pay(&info, &mint_price.denom)?;
-    if amount != mint_price.amount {
-        return Err(ContractError::WrongAmount {});
+    // Only whole units are credited, the remainder is kept for later
+    let pending = Uint128::one() * (index - token_index);
+    Ok((pending, token_index + Decimal::from_ratio(pending, 1u128)))
+}
+
+/// Returns the balance of an NFT, including its share of early-break penalties
+pub fn token_balance(storage: &dyn Storage, token_id: &str) -> StdResult<Uint128> {
+    let (pending, _) = pending_penalty_share(storage, token_id)?;
+    Ok(BALANCES.may_load(storage, token_id)?.unwrap_or_default() + pending)
+}
+
+/// Credits an NFT with its share of early-break penalties, returning the new balance
+pub fn settle_token(storage: &mut dyn Storage, token_id: &str) -> StdResult<Uint128> {
+    let (pending, token_index) = pending_penalty_share(storage, token_id)?;
+    TOKEN_PENALTY_INDEX.save(storage, token_id, &token_index)?;
+    BALANCES.update(storage, token_id, |balance| {
+        Ok(balance.unwrap_or_default() + pending)
+    })
+}
+
+/// Locks an NFT so it can't be broken open until `until`, only callable by the owner.
+/// An existing lock can only be extended.
+pub fn execute_lock(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+    until: Timestamp,
+) -> Result<Response, ContractError> {
+    let token = Cw721Contract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+    if token.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
     }
 
-    // Load previous_token_id, incrementing it, saving the new value, and returning the result
-    let next_token_id = PREVIOUS_TOKEN_ID.update(deps.storage, |previous_id| {
-        Ok::<u64, StdError>(previous_id + 1)
-    })?;
+    if until <= env.block.time {
+        return Err(ContractError::InvalidLock {});
+    }
+    match LOCKS.may_load(deps.storage, &token_id)? {
+        Some(current) if until < current => return Err(ContractError::InvalidLock {}),
+        Some(_) => {}
+        None => LOCK_STARTS.save(deps.storage, &token_id, &env.block.time)?,
+    }
+    LOCKS.save(deps.storage, &token_id, &until)?;
+    record_activity(deps.storage, &env, &token_id, &info.sender)?;
 
-    // Check the collection hasn't been minted out
-    let max_supply = MAX_NFT_SUPPLY.may_load(deps.storage)?;
-    if let Some(max_supply) = max_supply {
-        if next_token_id > max_supply {
-            return Err(ContractError::MintedOut {});
-        }
+    Ok(Response::default()
+        .add_attribute("action", "lock")
+        .add_attribute("token_id", token_id)
+        .add_attribute("until", until.to_string()))
+}
+
+pub fn execute_update_fee(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    fee: Option<FeeMsg>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::TreasuryManager)?;
+
+    match fee {
+        Some(fee) => FEE_CONFIG.save(deps.storage, &validate_fee(deps.api, fee)?)?,
+        None => FEE_CONFIG.remove(deps.storage),
     }
 
-    // Pay out funds to creator or recipient of sale funds
-    let recipient = SALE_FUNDS_RECIPIENT.load(deps.storage)?;
-    let msg = BankMsg::Send {
-        to_address: recipient.to_string(),
-        amount: info.funds.clone(),
-    };
+    Ok(Response::default().add_attribute("action", "update_fee"))
+}
 
-    // Mint the NFT and assign to the sender
+fn validate_fee(api: &dyn Api, fee: FeeMsg) -> Result<FeeConfig, ContractError> {
+    if fee.bps > MAX_FEE_BPS {
+        return Err(ContractError::FeeTooHigh { max: MAX_FEE_BPS });
+    }
+    Ok(FeeConfig {
+        bps: fee.bps,
+        treasury: api.addr_validate(&fee.treasury)?,
+        waive_after_maturity: fee.waive_after_maturity,
+        min_lock_duration: fee.min_lock_duration,
+    })
+}
+
+/// Combines two NFTs owned by the sender, moving everything held by `source`
+/// into `target` and burning `source` without paying out
+pub fn execute_merge(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    source: String,
+    target: String,
+) -> Result<Response, ContractError> {
     let base = Cw721Contract::default();
+
+    if source == target {
+        return Err(ContractError::InvalidMerge {});
+    }
+    // Only the owner of both NFTs can merge them
+    for token_id in [&source, &target] {
+        if base.tokens.load(deps.storage, token_id)?.owner != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+    }
+    // Refunds are per NFT, so the mint payment can't be moved
+    if MINT_PAYMENTS.has(deps.storage, &source)
+        && raise_status(deps.storage, &env)? != Some(RaiseStatus::Succeeded)
+    {
+        return Err(ContractError::MintRefundable {});
+    }
+    // Funds can't be moved out from under a guardian
+    if let Some(guardian) = GUARDIANS.may_load(deps.storage, &source)? {
+        if GUARDIANS.may_load(deps.storage, &target)? != Some(guardian) {
+            return Err(ContractError::GuardianApprovalRequired {});
+        }
+    }
+
+    // Move the balance, this also re-evaluates the growth stage of the target
+    let amount = settle_token(deps.storage, &source)?;
+    let balance = credit_token(deps.storage, &target, amount)?;
+
+    // Move the contribution records
+    let contributions = CONTRIBUTIONS
+        .prefix(&source)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (contributor, amount) in contributions {
+        record_contribution(deps.storage, &target, &contributor, amount)?;
+        CONTRIBUTIONS.remove(deps.storage, (&source, &contributor));
+    }
+
+    // Savings plans for the source now save into the target
+    let plan_ids = TOKEN_PLANS
+        .prefix(&source)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for plan_id in plan_ids {
+        let mut plan = PLANS.load(deps.storage, plan_id)?;
+        plan.token_id = target.clone();
+        PLANS.save(deps.storage, plan_id, &plan)?;
+        TOKEN_PLANS.remove(deps.storage, (&source, plan_id));
+        TOKEN_PLANS.save(deps.storage, (&target, plan_id), &Empty {})?;
+    }
+
+    // The later maturity wins, and the moved funds count as a deposit into the target
+    if let Some(source_lock) = LOCKS.may_load(deps.storage, &source)? {
+        LOCKS.update(deps.storage, &target, |target_lock| -> StdResult<_> {
+            Ok(target_lock.map_or(source_lock, |target_lock| target_lock.max(source_lock)))
+        })?;
+    }
+    restart_lock(deps.storage, &env, &target)?;
+    record_activity(deps.storage, &env, &target, &info.sender)?;
+
+    // Burn the source without paying out, the balance has moved to the target
     base.execute(
-        deps,
+        deps.branch(),
         env,
-        info.clone(),
-        ExecuteMsg::Mint {
-            token_id: next_token_id.to_string(),
-            owner: info.sender.to_string(),
-            // Formats the NFT token_uri (which links to the metadata) based on the token_id and the initial
-            // state for the dynamic NFT (in this case NFT trees start out as seedlings)
-            token_uri: Some(format!(
-                "{}/{}/{}",
-                base_url,
-                next_token_id.to_string(),
-                "seedling.json"
-            )),
-            extension: MetadataExt {},
+        info,
+        BaseExecuteMsg::Burn {
+            token_id: source.clone(),
         },
     )?;
+    remove_token_state(deps.storage, &source)?;
 
-    Ok(Response::default().add_message(msg))
+    Ok(Response::default()
+        .add_attribute("action", "merge")
+        .add_attribute("source", source)
+        .add_attribute("target", target)
+        .add_attribute("amount", amount)
+        .add_attribute("balance", balance))
 }
 
-pub fn execute_deposit(
+pub fn execute_transfer_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    token_id: String,
+    expected_balance: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    check_transfer(deps.storage, &env, &token_id, expected_balance)?;
+
+    // Pass off to default cw721 transfer implementation, handles checking ownership
+    Cw721Contract::default()
+        .execute(
+            deps,
+            env,
+            info,
+            BaseExecuteMsg::TransferNft {
+                recipient,
+                token_id,
+            },
+        )
+        .map_err(Into::into)
+}
+
+pub fn execute_send_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    token_id: String,
+    msg: Binary,
+    expected_balance: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    check_transfer(deps.storage, &env, &token_id, expected_balance)?;
+
+    // Pass off to default cw721 send implementation, handles checking ownership
+    Cw721Contract::default()
+        .execute(
+            deps,
+            env,
+            info,
+            BaseExecuteMsg::SendNft {
+                contract,
+                token_id,
+                msg,
+            },
+        )
+        .map_err(Into::into)
+}
+
+/// Moves a soulbound NFT to a new owner, only callable by a config admin and only
+/// if the collection allows admin recovery
+pub fn execute_recovery_transfer(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     token_id: String,
+    recipient: String,
 ) -> Result<Response, ContractError> {
-    // Check that funds were actually sent
-    let denom = DEPOSIT_DENOM.load(deps.storage)?;
-    // Check the right kind of funds were sent
-    let amount = must_pay(&info, &denom)?;
+    assert_role(deps.as_ref(), &info.sender, Role::ConfigAdmin)?;
+    if !ADMIN_RECOVERY.load(deps.storage)? || !SOULBOUND.load(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
 
-    let base = Cw721Contract::default();
+    let recipient = deps.api.addr_validate(&recipient)?;
+    reassign_token(deps.storage, &token_id, &recipient)?;
 
-    // Load base URL for token metadata
-    let base_url = BASE_URL.load(deps.storage)?;
+    Ok(Response::default()
+        .add_attribute("action", "recovery_transfer")
+        .add_attribute("token_id", token_id)
+        .add_attribute("recipient", recipient))
+}
 
-    // Check that the token exists
-    let mut token = base.tokens.load(deps.storage, &token_id)?;
+/// Gives an NFT to a new owner without the owner's permission, clearing approvals
+pub fn reassign_token(
+    storage: &mut dyn Storage,
+    token_id: &str,
+    new_owner: &Addr,
+) -> Result<(), ContractError> {
+    let base = Cw721Contract::default();
+    let mut token = base.tokens.load(storage, token_id)?;
+    token.owner = new_owner.clone();
+    token.approvals = vec![];
+    base.tokens.save(storage, token_id, &token)?;
+    Ok(())
+}
 
-    BALANCES.update(deps.storage, &token_id, |balance| -> StdResult<_> {
-        let new_balance = balance.unwrap_or_default() + amount;
+/// Checks an NFT can change hands: the balance matches what the buyer expects,
+/// and funds haven't been withdrawn from it too recently
+fn check_transfer(
+    storage: &dyn Storage,
+    env: &Env,
+    token_id: &str,
+    expected_balance: Option<Uint128>,
+) -> Result<(), ContractError> {
+    let balance = token_balance(storage, token_id)?;
+    match expected_balance {
+        Some(expected) if expected != balance => {
+            return Err(ContractError::BalanceMismatch {
+                expected,
+                actual: balance,
+            });
+        }
+        None if !balance.is_zero() && REQUIRE_EXPECTED_BALANCE.load(storage)? => {
+            return Err(ContractError::ExpectedBalanceRequired {});
+        }
+        _ => {}
+    }
 
-        // Native token micro units are typically 6 decimal places
-        // Check if balance is greater than 1
-        if new_balance > Uint128::new(1000000) {
-            token.token_uri = Some(format!("{}/{}/{}", base_url, token_id, "sapling.json"));
-        } else if new_balance > Uint128::new(10000000) {
-            token.token_uri = Some(format!("{}/{}/{}", base_url, token_id, "tree.json"));
-        } else if new_balance > Uint128::new(100000000) {
-            token.token_uri = Some(format!("{}/{}/{}", base_url, token_id, "fullgrown.json"));
+    if let Some(cooldown) = TRANSFER_COOLDOWN.may_load(storage)? {
+        if let Some(last_withdrawal) = LAST_WITHDRAWALS.may_load(storage, token_id)? {
+            let until = last_withdrawal.plus_seconds(cooldown);
+            if env.block.time < until {
+                return Err(ContractError::TransferCooldown { until });
+            }
         }
+    }
 
-        Ok(new_balance)
-    })?;
+    Ok(())
+}
 
-    base.tokens.save(deps.storage, &token_id, &token)?;
+/// Sets the guardian who approves breaking open an NFT. The owner can set a
+/// guardian if there is none, after that only the guardian can change it.
+pub fn execute_set_guardian(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    token_id: String,
+    guardian: Option<String>,
+) -> Result<Response, ContractError> {
+    let token = Cw721Contract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+    match GUARDIANS.may_load(deps.storage, &token_id)? {
+        Some(current) if current != info.sender => return Err(ContractError::Unauthorized {}),
+        None if token.owner != info.sender => return Err(ContractError::Unauthorized {}),
+        _ => {}
+    }
+
+    match &guardian {
+        Some(guardian) => {
+            let guardian = deps.api.addr_validate(guardian)?;
+            GUARDIANS.save(deps.storage, &token_id, &guardian)?;
+        }
+        None => GUARDIANS.remove(deps.storage, &token_id),
+    }
 
     Ok(Response::default()
-        .add_attribute("action", "deposit")
-        .add_attribute("value", amount.to_string()))
+        .add_attribute("action", "set_guardian")
+        .add_attribute("token_id", token_id)
+        .add_attribute("guardian", guardian.unwrap_or_else(|| "none".to_string())))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        // Optionally override a default cw721-base query
+/// Asks the guardian of an NFT for approval to break it open, only callable by the owner
+pub fn execute_request_break(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let token = Cw721Contract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+    if token.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !GUARDIANS.has(deps.storage, &token_id) {
+        return Err(ContractError::NoGuardian {});
+    }
+
+    BREAK_REQUESTS.save(
+        deps.storage,
+        &token_id,
+        &BreakRequest {
+            requested_at: env.block.time,
+            approved: false,
+        },
+    )?;
+
+    Ok(Response::default()
+        .add_attribute("action", "request_break")
+        .add_attribute("token_id", token_id))
+}
+
+/// Approves a pending request to break open an NFT, only callable by the guardian
+pub fn execute_approve_break(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    if GUARDIANS.may_load(deps.storage, &token_id)? != Some(info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut request = BREAK_REQUESTS.load(deps.storage, &token_id)?;
+    request.approved = true;
+    BREAK_REQUESTS.save(deps.storage, &token_id, &request)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "approve_break")
+        .add_attribute("token_id", token_id))
+}
+
+/// Checks a guarded NFT's break request was approved or has timed out
+fn check_break_approved(
+    storage: &dyn Storage,
+    env: &Env,
+    token_id: &str,
+) -> Result<(), ContractError> {
+    if !GUARDIANS.has(storage, token_id) {
+        return Ok(());
+    }
+    let request = BREAK_REQUESTS
+        .may_load(storage, token_id)?
+        .ok_or(ContractError::GuardianApprovalRequired {})?;
+    if request.approved {
+        return Ok(());
+    }
+    match GUARDIAN_TIMEOUT.may_load(storage)? {
+        Some(timeout) if env.block.time >= request.requested_at.plus_seconds(timeout) => Ok(()),
+        _ => Err(ContractError::GuardianApprovalRequired {}),
+    }
+}
+
+/// Sets the addresses that can recover an NFT, only callable by the owner.
+/// Any signatures collected under the previous setup are discarded.
+pub fn execute_set_recovery(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    token_id: String,
+    recovery: Option<RecoveryMsg>,
+) -> Result<Response, ContractError> {
+    let token = Cw721Contract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+    if token.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    clear_recovery(deps.storage, &token_id)?;
+    match recovery {
+        Some(recovery) => {
+            let mut addresses = recovery
+                .addresses
+                .iter()
+                .map(|address| deps.api.addr_validate(address))
+                .collect::<StdResult<Vec<_>>>()?;
+            addresses.sort();
+            addresses.dedup();
+            if recovery.threshold == 0 || recovery.threshold as usize > addresses.len() {
+                return Err(ContractError::InvalidRecovery {});
+            }
+            RECOVERY_CONFIGS.save(
+                deps.storage,
+                &token_id,
+                &RecoveryConfig {
+                    owner: token.owner,
+                    addresses,
+                    threshold: recovery.threshold,
+                    delay: recovery.delay,
+                },
+            )?;
+        }
+        None => RECOVERY_CONFIGS.remove(deps.storage, &token_id),
+    }
+
+    Ok(Response::default()
+        .add_attribute("action", "set_recovery")
+        .add_attribute("token_id", token_id))
+}
+
+/// Signs for moving an NFT to a new owner, only callable by its recovery addresses.
+/// The delay starts once enough addresses agree, after it has passed signing
+/// again moves the NFT.
+pub fn execute_recover(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+    new_owner: String,
+) -> Result<Response, ContractError> {
+    let token = Cw721Contract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+    let config = recovery_config(deps.storage, &token_id, &token.owner)?
+        .ok_or(ContractError::Unauthorized {})?;
+    if !config.addresses.contains(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    RECOVERY_VOTES.save(deps.storage, (&token_id, &info.sender), &new_owner)?;
+
+    let res = Response::default()
+        .add_attribute("token_id", &token_id)
+        .add_attribute("new_owner", &new_owner);
+
+    // Complete a recovery that has waited out its delay, as long as enough
+    // addresses still back it. Otherwise the pending recovery is dropped.
+    if let Some(pending) = PENDING_RECOVERIES.may_load(deps.storage, &token_id)? {
+        let backed = count_recovery_votes(deps.storage, &token_id, &pending.new_owner)?
+            >= config.threshold as usize;
+        if !backed {
+            PENDING_RECOVERIES.remove(deps.storage, &token_id);
+        } else if pending.new_owner == new_owner {
+            if env.block.time < pending.ready_at {
+                return Err(ContractError::RecoveryDelay {
+                    until: pending.ready_at,
+                });
+            }
+            clear_recovery(deps.storage, &token_id)?;
+            RECOVERY_CONFIGS.remove(deps.storage, &token_id);
+            reassign_token(deps.storage, &token_id, &new_owner)?;
+            return Ok(res.add_attribute("action", "recover"));
+        }
+    }
+
+    // Start the delay once enough addresses agree on the new owner
+    let votes = count_recovery_votes(deps.storage, &token_id, &new_owner)?;
+    if votes >= config.threshold as usize {
+        let ready_at = env.block.time.plus_seconds(config.delay);
+        PENDING_RECOVERIES.save(
+            deps.storage,
+            &token_id,
+            &PendingRecovery {
+                new_owner,
+                ready_at,
+            },
+        )?;
+        return Ok(res
+            .add_attribute("action", "start_recovery")
+            .add_attribute("ready_at", ready_at.to_string()));
+    }
+
+    Ok(res.add_attribute("action", "sign_recovery"))
+}
+
+/// Counts the addresses voting to recover an NFT to `new_owner`
+fn count_recovery_votes(
+    storage: &dyn Storage,
+    token_id: &str,
+    new_owner: &Addr,
+) -> StdResult<usize> {
+    RECOVERY_VOTES
+        .prefix(token_id)
+        .range(storage, None, None, Order::Ascending)
+        .try_fold(0, |votes, item| {
+            let (_, voted) = item?;
+            Ok(if voted == *new_owner {
+                votes + 1
+            } else {
+                votes
+            })
+        })
+}
+
+/// Cancels a recovery in progress, discarding all signatures, only callable by the owner
+pub fn execute_cancel_recovery(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let token = Cw721Contract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+    if token.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    clear_recovery(deps.storage, &token_id)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "cancel_recovery")
+        .add_attribute("token_id", token_id))
+}
+
+/// Loads the recovery setup of an NFT, ignoring setups made by a previous owner
+fn recovery_config(
+    storage: &dyn Storage,
+    token_id: &str,
+    owner: &Addr,
+) -> StdResult<Option<RecoveryConfig>> {
+    Ok(RECOVERY_CONFIGS
+        .may_load(storage, token_id)?
+        .filter(|config| config.owner == *owner))
+}
+
+/// Discards the signatures and any pending recovery of an NFT
+fn clear_recovery(storage: &mut dyn Storage, token_id: &str) -> StdResult<()> {
+    let signers = RECOVERY_VOTES
+        .prefix(token_id)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for signer in signers {
+        RECOVERY_VOTES.remove(storage, (token_id, &signer));
+    }
+    PENDING_RECOVERIES.remove(storage, token_id);
+    Ok(())
+}
+
+/// Names who inherits an NFT if the owner stops interacting with it, only
+/// callable by the owner. Naming a beneficiary counts as activity.
+pub fn execute_set_beneficiary(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+    beneficiary: Option<BeneficiaryMsg>,
+) -> Result<Response, ContractError> {
+    let token = Cw721Contract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+    if token.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let res = Response::default()
+        .add_attribute("action", "set_beneficiary")
+        .add_attribute("token_id", &token_id);
+    let res = match beneficiary {
+        Some(beneficiary) => {
+            let address = deps.api.addr_validate(&beneficiary.address)?;
+            BENEFICIARIES.save(
+                deps.storage,
+                &token_id,
+                &Beneficiary {
+                    owner: token.owner,
+                    address: address.clone(),
+                    inactivity_period: beneficiary.inactivity_period,
+                },
+            )?;
+            res.add_attribute("beneficiary", address)
+        }
+        None => {
+            BENEFICIARIES.remove(deps.storage, &token_id);
+            res.add_attribute("beneficiary", "none")
+        }
+    };
+    record_activity(deps.storage, &env, &token_id, &info.sender)?;
+
+    Ok(res)
+}
+
+/// Resets the inactivity period of an NFT, only callable by the owner
+pub fn execute_ping(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let token = Cw721Contract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+    if token.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    record_activity(deps.storage, &env, &token_id, &info.sender)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "ping")
+        .add_attribute("token_id", token_id))
+}
+
+/// Moves an NFT to its beneficiary once the owner has been inactive for the
+/// inactivity period, only callable by the beneficiary
+pub fn execute_claim_inheritance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let token = Cw721Contract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+    let beneficiary = beneficiary(deps.storage, &token_id, &token.owner)?
+        .filter(|beneficiary| beneficiary.address == info.sender)
+        .ok_or(ContractError::Unauthorized {})?;
+
+    let until = inheritance_claimable_at(deps.storage, &token_id, &beneficiary)?;
+    if env.block.time < until {
+        return Err(ContractError::InheritanceNotClaimable { until });
+    }
+
+    BENEFICIARIES.remove(deps.storage, &token_id);
+    reassign_token(deps.storage, &token_id, &beneficiary.address)?;
+    record_activity(deps.storage, &env, &token_id, &beneficiary.address)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "claim_inheritance")
+        .add_attribute("token_id", token_id)
+        .add_attribute("previous_owner", token.owner)
+        .add_attribute("new_owner", beneficiary.address))
+}
+
+/// Loads the beneficiary of an NFT, ignoring beneficiaries named by a previous owner
+fn beneficiary(
+    storage: &dyn Storage,
+    token_id: &str,
+    owner: &Addr,
+) -> StdResult<Option<Beneficiary>> {
+    Ok(BENEFICIARIES
+        .may_load(storage, token_id)?
+        .filter(|beneficiary| beneficiary.owner == *owner))
+}
+
+/// When the beneficiary can claim an NFT if the owner stays inactive
+fn inheritance_claimable_at(
+    storage: &dyn Storage,
+    token_id: &str,
+    beneficiary: &Beneficiary,
+) -> StdResult<Timestamp> {
+    // Naming a beneficiary records activity, so there is always a starting point
+    let last_activity = LAST_ACTIVITY.load(storage, token_id)?;
+    Ok(last_activity.plus_seconds(beneficiary.inactivity_period))
+}
+
+/// Records that the owner interacted with an NFT, other accounts are ignored
+fn record_activity(
+    storage: &mut dyn Storage,
+    env: &Env,
+    token_id: &str,
+    sender: &Addr,
+) -> StdResult<()> {
+    let token = Cw721Contract::default().tokens.load(storage, token_id)?;
+    if token.owner == *sender {
+        LAST_ACTIVITY.save(storage, token_id, &env.block.time)?;
+    }
+    Ok(())
+}
+
+/// Lets operators choose who receives the payout when they break open an NFT,
+/// only callable by the owner. Cleared when the NFT changes owner.
+pub fn execute_allow_operator_payout(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    token_id: String,
+    allowed: bool,
+) -> Result<Response, ContractError> {
+    let token = Cw721Contract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+    if token.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if allowed {
+        OPERATOR_PAYOUT_OPT_INS.save(deps.storage, &token_id, &token.owner)?;
+    } else {
+        OPERATOR_PAYOUT_OPT_INS.remove(deps.storage, &token_id);
+    }
+
+    Ok(Response::default()
+        .add_attribute("action", "allow_operator_payout")
+        .add_attribute("token_id", token_id)
+        .add_attribute("allowed", allowed.to_string()))
+}
+
+/// Removes the piggy bank state of a burned NFT
+fn remove_token_state(storage: &mut dyn Storage, token_id: &str) -> StdResult<()> {
+    BALANCES.remove(storage, token_id);
+    TOKEN_MIN_DEPOSITS.remove(storage, token_id);
+    OPERATOR_PAYOUT_OPT_INS.remove(storage, token_id);
+    LOCKS.remove(storage, token_id);
+    LOCK_STARTS.remove(storage, token_id);
+    TOKEN_PENALTY_INDEX.remove(storage, token_id);
+    LAST_WITHDRAWALS.remove(storage, token_id);
+    GUARDIANS.remove(storage, token_id);
+    BREAK_REQUESTS.remove(storage, token_id);
+    RECOVERY_CONFIGS.remove(storage, token_id);
+    BENEFICIARIES.remove(storage, token_id);
+    LAST_ACTIVITY.remove(storage, token_id);
+    MINT_PAYMENTS.remove(storage, token_id);
+    clear_recovery(storage, token_id)
+}
+
+// NOTE: in real life, add some randomness to minting, otherwise people will game it
+pub fn execute_mint(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    guardian: Option<String>,
+) -> Result<Response, ContractError> {
+    let guardian = guardian
+        .map(|guardian| deps.api.addr_validate(&guardian))
+        .transpose()?;
+
+    // Load mint_price
+    let mint_price = MINT_PRICE.load(deps.storage)?;
+    let auction = AUCTION.may_load(deps.storage)?;
+
+    // Mints paid in another accepted denom are sold at that denom's price
+    let payment = one_coin(&info)?;
+    if payment.denom != mint_price.denom {
+        let price = PAYMENT_OPTIONS
+            .load(deps.storage)?
+            .into_iter()
+            .find_map(|option| match option {
+                PaymentOption::Native { denom, amount } if denom == payment.denom => Some(amount),
+                _ => None,
+            })
+            .ok_or(PaymentError::MissingDenom(mint_price.denom))?;
+        if payment.amount != price {
+            return Err(ContractError::WrongAmount {});
+        }
+        count_wallet_mints(deps.storage, &info.sender, 1)?;
+        count_sale(deps.storage)?;
+        let token_id = mint_token(deps.storage, &info.sender, guardian.as_ref())?;
+        let recipient = SALE_FUNDS_RECIPIENT.load(deps.storage)?;
+        return Ok(Response::default()
+            .add_message(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![payment.clone()],
+            })
+            .add_attribute("action", "mint")
+            .add_attribute("owner", info.sender)
+            .add_attribute("token_id", token_id)
+            .add_attribute("price", payment.to_string()));
+    }
+
+    // Check the right amount of funds were sent, auctions and bonding curves
+    // refund overpayments since the price can move before the mint executes
+    let paid = must_pay(&info, &mint_price.denom)?;
+    let price = current_price(deps.storage, &env)?.amount;
+    let exact = auction.is_none() && BONDING_CURVE.may_load(deps.storage)?.is_none();
+    if paid < price || (exact && paid != price) {
+        return Err(ContractError::WrongAmount {});
+    }
+    let change = paid - price;
+
+    // Mint the NFT and assign to the sender
+    count_wallet_mints(deps.storage, &info.sender, 1)?;
+    let mut res = Response::default()
+        .add_attribute("action", "mint")
+        .add_attribute("owner", &info.sender)
+        .add_attribute("price", price);
+    if !change.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom: mint_price.denom.clone(),
+                amount: change,
+            }],
+        });
+    }
+    let (res, amount) = sell_token(
+        deps.storage,
+        &env,
+        &info.sender,
+        &info.sender,
+        guardian.as_ref(),
+        price,
+        res,
+    )?;
+
+    // Pay out funds to creator or recipient of sale funds
+    let recipient = SALE_FUNDS_RECIPIENT.load(deps.storage)?;
+    if amount.is_zero() {
+        return Ok(res);
+    }
+    Ok(res.add_message(BankMsg::Send {
+        to_address: recipient.to_string(),
+        amount: vec![Coin {
+            denom: mint_price.denom,
+            amount,
+        }],
+    }))
+}
+
+/// Mints `count` NFTs to `recipient` in one go, paid for in the mint denom.
+/// Proceeds are paid out in a single message.
+pub fn execute_purchase_batch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    count: u64,
+    recipient: Option<String>,
+    guardian: Option<String>,
+) -> Result<Response, ContractError> {
+    if count == 0 {
+        return Err(ContractError::WrongAmount {});
+    }
+    let owner = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => info.sender.clone(),
+    };
+    let guardian = guardian
+        .map(|guardian| deps.api.addr_validate(&guardian))
+        .transpose()?;
+
+    // Bonding curve prices rise with every NFT sold, so each one is priced in turn
+    let mint_price = MINT_PRICE.load(deps.storage)?;
+    let bonding_curve = BONDING_CURVE.may_load(deps.storage)?;
+    let tokens_sold = TOKENS_SOLD.load(deps.storage)?;
+    let prices = (1..=count)
+        .map(|n| match bonding_curve {
+            Some(_) => price_at(deps.storage, tokens_sold + n).map(|price| price.amount),
+            None => current_price(deps.storage, &env).map(|price| price.amount),
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    let total = prices
+        .iter()
+        .try_fold(Uint128::zero(), |total, price| total.checked_add(*price))?;
+
+    // Same as a single mint, only auctions and bonding curves refund overpayments
+    let paid = must_pay(&info, &mint_price.denom)?;
+    let exact = AUCTION.may_load(deps.storage)?.is_none() && bonding_curve.is_none();
+    if paid < total || (exact && paid != total) {
+        return Err(ContractError::WrongAmount {});
+    }
+    let change = paid - total;
+    count_wallet_mints(deps.storage, &info.sender, count)?;
+
+    let mut res = Response::default()
+        .add_attribute("action", "purchase_batch")
+        .add_attribute("owner", &owner)
+        .add_attribute("count", count.to_string())
+        .add_attribute("price", total);
+    if !change.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom: mint_price.denom.clone(),
+                amount: change,
+            }],
+        });
+    }
+    let mut payout = Uint128::zero();
+    for price in prices {
+        let (sold, amount) = sell_token(
+            deps.storage,
+            &env,
+            &info.sender,
+            &owner,
+            guardian.as_ref(),
+            price,
+            res,
+        )?;
+        res = sold;
+        payout += amount;
+    }
+
+    let recipient = SALE_FUNDS_RECIPIENT.load(deps.storage)?;
+    if !payout.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin {
+                denom: mint_price.denom,
+                amount: payout,
+            }],
+        });
+    }
+    Ok(res)
+}
+
+/// Counts `count` more mints against the buyer's wallet, failing past the per-wallet cap
+fn count_wallet_mints(
+    storage: &mut dyn Storage,
+    buyer: &Addr,
+    count: u64,
+) -> Result<(), ContractError> {
+    let minted = WALLET_MINTS.may_load(storage, buyer)?.unwrap_or_default() + count;
+    if let Some(max) = MAX_MINTS_PER_WALLET.may_load(storage)? {
+        if minted > max {
+            return Err(ContractError::WalletLimitReached { max });
+        }
+    }
+    WALLET_MINTS.save(storage, buyer, &minted)?;
+    Ok(())
+}
+
+/// Mints an NFT sold at `price` in the mint denom to `owner`, guarded by
+/// `guardian` if given. Holds any auction rebate, seeds the NFT and escrows
+/// the proceeds during a minimum raise, returning what can be paid out to the
+/// sale funds recipient right away.
+fn sell_token(
+    storage: &mut dyn Storage,
+    env: &Env,
+    buyer: &Addr,
+    owner: &Addr,
+    guardian: Option<&Addr>,
+    price: Uint128,
+    mut res: Response,
+) -> Result<(Response, Uint128), ContractError> {
+    let denom = MINT_PRICE.load(storage)?.denom;
+    count_sale(storage)?;
+    let token_id = mint_token(storage, owner, guardian)?;
+    res = res.add_attribute("token_id", &token_id);
+
+    // Hold what was paid above the floor until the auction settles
+    let mut amount = price;
+    if let Some(auction) = AUCTION.may_load(storage)?.filter(|auction| auction.rebate) {
+        let held = price - auction.floor_price;
+        AUCTION_PURCHASES.update(storage, buyer, |purchase| -> StdResult<_> {
+            let mut purchase = purchase.unwrap_or(AuctionPurchase {
+                count: 0,
+                held: Uint128::zero(),
+            });
+            purchase.count += 1;
+            purchase.held = purchase.held.checked_add(held)?;
+            Ok(purchase)
+        })?;
+        let mut progress = AUCTION_PROGRESS.load(storage)?;
+        progress.sales += 1;
+        // Selling out settles the auction at the price of the last sale
+        let sold_out = MAX_NFT_SUPPLY.may_load(storage)? == Some(TOKENS_SOLD.load(storage)?);
+        if sold_out && progress.clearing_price.is_none() {
+            progress.clearing_price = Some(price);
+        }
+        AUCTION_PROGRESS.save(storage, &progress)?;
+        increase_total_deposits(storage, &denom, held)?;
+        amount = auction.floor_price;
+        res = res.add_attribute("held", held);
+    }
+
+    // Seed the new piggy bank with part of the proceeds, this sets its starting growth stage
+    if let Some(mint_seed_bps) = MINT_SEED.may_load(storage)? {
+        let seed = amount.multiply_ratio(mint_seed_bps, MAX_BPS);
+        if !seed.is_zero() {
+            credit_token(storage, &token_id, seed)?;
+            record_contribution(storage, &token_id, buyer, seed)?;
+            increase_total_deposits(storage, &denom, seed)?;
+            amount -= seed;
+            res = res.add_attribute("seed", seed);
+        }
+    }
+
+    // Hold the proceeds until the minimum raise succeeds
+    match raise_status(storage, env)? {
+        Some(RaiseStatus::Failed) => Err(ContractError::RaiseFailed {}),
+        Some(RaiseStatus::Pending) => {
+            MINT_PAYMENTS.save(storage, &token_id, &amount)?;
+            RAISE_MINTED.update(storage, |minted| -> StdResult<_> { Ok(minted + 1) })?;
+            ESCROWED_PROCEEDS.update(storage, |escrowed| -> StdResult<_> {
+                Ok(escrowed.checked_add(amount)?)
+            })?;
+            increase_total_deposits(storage, &denom, amount)?;
+            Ok((res.add_attribute("escrowed", amount), Uint128::zero()))
+        }
+        _ => Ok((res, amount)),
+    }
+}
+
+/// Buys an NFT with a cw20 payment option, called by the cw20 token when
+/// tokens are sent to this contract
+pub fn execute_receive(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // The sender is the cw20 token, only accepted tokens can pay
+    let price = PAYMENT_OPTIONS
+        .load(deps.storage)?
+        .into_iter()
+        .find_map(|option| match option {
+            PaymentOption::Cw20 { address, amount } if address == info.sender => Some(amount),
+            _ => None,
+        })
+        .ok_or(ContractError::Unauthorized {})?;
+
+    match from_binary(&msg.msg)? {
+        ReceiveMsg::Mint { guardian } => {
+            if msg.amount != price {
+                return Err(ContractError::WrongAmount {});
+            }
+            let buyer = deps.api.addr_validate(&msg.sender)?;
+            let guardian = guardian
+                .map(|guardian| deps.api.addr_validate(&guardian))
+                .transpose()?;
+            count_wallet_mints(deps.storage, &buyer, 1)?;
+            count_sale(deps.storage)?;
+            let token_id = mint_token(deps.storage, &buyer, guardian.as_ref())?;
+            let recipient = SALE_FUNDS_RECIPIENT.load(deps.storage)?;
+
+            Ok(Response::default()
+                .add_message(WasmMsg::Execute {
+                    contract_addr: info.sender.to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: recipient.to_string(),
+                        amount: msg.amount,
+                    })?,
+                    funds: vec![],
+                })
+                .add_attribute("action", "mint")
+                .add_attribute("owner", buyer)
+                .add_attribute("token_id", token_id)
+                .add_attribute("token", info.sender)
+                .add_attribute("price", msg.amount))
+        }
+    }
+}
+
+/// The price to mint an NFT right now, following the Dutch auction or bonding
+/// curve if there is one
+pub fn current_price(storage: &dyn Storage, env: &Env) -> StdResult<Coin> {
+    if BONDING_CURVE.may_load(storage)?.is_some() {
+        return price_at(storage, TOKENS_SOLD.load(storage)? + 1);
+    }
+    let mint_price = MINT_PRICE.load(storage)?;
+    let auction = match AUCTION.may_load(storage)? {
+        Some(auction) => auction,
+        None => return Ok(mint_price),
+    };
+    let periods = match auction.decay_interval {
+        Duration::Height(interval) => (env.block.height - auction.start_height) / interval,
+        Duration::Time(interval) => {
+            (env.block.time.seconds() - auction.start_time.seconds()) / interval
+        }
+    };
+    let decayed = auction.decay.saturating_mul(Uint128::from(periods));
+    Ok(Coin {
+        denom: mint_price.denom,
+        amount: auction
+            .start_price
+            .saturating_sub(decayed)
+            .max(auction.floor_price),
+    })
+}
+
+/// The price to mint the nth NFT, counting from 1. Without a bonding curve
+/// every NFT costs the mint price.
+pub fn price_at(storage: &dyn Storage, token_number: u64) -> StdResult<Coin> {
+    let mint_price = MINT_PRICE.load(storage)?;
+    let steps = token_number.saturating_sub(1);
+    let amount = match BONDING_CURVE.may_load(storage)? {
+        Some(BondingCurve::Linear {
+            base_price,
+            increment,
+        }) => base_price.checked_add(increment.checked_mul(Uint128::from(steps))?)?,
+        Some(BondingCurve::Exponential {
+            base_price,
+            growth_bps,
+        }) => {
+            let growth = Decimal::from_ratio(MAX_BPS + growth_bps, MAX_BPS);
+            let steps = u32::try_from(steps)
+                .map_err(|_| StdError::generic_err("Token number is too large"))?;
+            base_price * growth.checked_pow(steps)?
+        }
+        None => mint_price.amount,
+    };
+    Ok(Coin {
+        denom: mint_price.denom,
+        amount,
+    })
+}
+
+/// The price a Dutch auction with rebates settled at, None if it is still running.
+/// It settles when the collection sells out or the price reaches the floor.
+fn clearing_price(storage: &dyn Storage, env: &Env) -> StdResult<Option<Uint128>> {
+    let auction = match AUCTION.may_load(storage)? {
+        Some(auction) if auction.rebate => auction,
+        _ => return Ok(None),
+    };
+    if let Some(clearing_price) = AUCTION_PROGRESS.load(storage)?.clearing_price {
+        return Ok(Some(clearing_price));
+    }
+    let price = current_price(storage, env)?.amount;
+    Ok((price == auction.floor_price).then_some(price))
+}
+
+/// Refunds what the sender paid above the clearing price of a settled Dutch auction
+pub fn execute_claim_rebate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let clearing_price =
+        clearing_price(deps.storage, &env)?.ok_or(ContractError::AuctionNotSettled {})?;
+    let auction = AUCTION.load(deps.storage)?;
+    let purchase = AUCTION_PURCHASES
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NoRebate {})?;
+    AUCTION_PURCHASES.remove(deps.storage, &info.sender);
+
+    // Buyers keep paying the floor plus the clearing price's share above it
+    let owed = (clearing_price - auction.floor_price) * Uint128::from(purchase.count);
+    let rebate = purchase.held - owed;
+    let denom = MINT_PRICE.load(deps.storage)?.denom;
+    decrease_total_deposits(deps.storage, &denom, rebate)?;
+
+    let mut res = Response::default()
+        .add_attribute("action", "claim_rebate")
+        .add_attribute("buyer", &info.sender)
+        .add_attribute("clearing_price", clearing_price)
+        .add_attribute("rebate", rebate);
+    if !rebate.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom,
+                amount: rebate,
+            }],
+        });
+    }
+    Ok(res)
+}
+
+/// Where the minimum raise stands, None if the collection doesn't have one.
+/// Once the minimum is sold the raise has succeeded, even before the deadline.
+pub fn raise_status(storage: &dyn Storage, env: &Env) -> StdResult<Option<RaiseStatus>> {
+    let min_raise = match MIN_RAISE.may_load(storage)? {
+        Some(min_raise) => min_raise,
+        None => return Ok(None),
+    };
+    let status = if RAISE_MINTED.load(storage)? >= min_raise.min_tokens {
+        RaiseStatus::Succeeded
+    } else if env.block.time < min_raise.deadline {
+        RaiseStatus::Pending
+    } else {
+        RaiseStatus::Failed
+    };
+    Ok(Some(status))
+}
+
+/// Returns the escrowed mint payment refunded when an NFT is burned, if the
+/// raise failed. NFTs holding a mint payment can't be burned while the raise
+/// is pending, so the payment isn't lost.
+fn mint_refund(
+    storage: &dyn Storage,
+    env: &Env,
+    token_id: &str,
+) -> Result<Option<Coin>, ContractError> {
+    match raise_status(storage, env)? {
+        Some(RaiseStatus::Failed) => {}
+        Some(RaiseStatus::Pending) if MINT_PAYMENTS.has(storage, token_id) => {
+            return Err(ContractError::MintRefundable {})
+        }
+        _ => return Ok(None),
+    }
+    let amount = match MINT_PAYMENTS.may_load(storage, token_id)? {
+        Some(amount) => amount,
+        None => return Ok(None),
+    };
+    let denom = MINT_PRICE.load(storage)?.denom;
+    Ok(Some(Coin { denom, amount }))
+}
+
+/// Takes the mint refund of an NFT being burned out of escrow.
+/// The NFT no longer counts towards the raise.
+fn take_mint_refund(
+    storage: &mut dyn Storage,
+    token_id: &str,
+    mint_refund: &Coin,
+) -> StdResult<()> {
+    MINT_PAYMENTS.remove(storage, token_id);
+    RAISE_MINTED.update(storage, |minted| -> StdResult<_> { Ok(minted - 1) })?;
+    ESCROWED_PROCEEDS.update(storage, |escrowed| -> StdResult<_> {
+        Ok(escrowed.checked_sub(mint_refund.amount)?)
+    })?;
+    decrease_total_deposits(storage, &mint_refund.denom, mint_refund.amount)?;
+    Ok(())
+}
+
+/// Sends the escrowed mint proceeds to the sale funds recipient once the
+/// minimum raise has succeeded or the Dutch auction has settled, anyone can call this
+pub fn execute_release_proceeds(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut amount = Uint128::zero();
+    match raise_status(deps.storage, &env)? {
+        Some(RaiseStatus::Succeeded) => {
+            amount += ESCROWED_PROCEEDS.load(deps.storage)?;
+            ESCROWED_PROCEEDS.save(deps.storage, &Uint128::zero())?;
+        }
+        Some(_) => return Err(ContractError::RaiseNotSucceeded {}),
+        None => {}
+    }
+
+    // The recipient's share of what was held above the floor in an auction with rebates
+    if let Some(auction) = AUCTION
+        .may_load(deps.storage)?
+        .filter(|auction| auction.rebate)
+    {
+        let clearing_price =
+            clearing_price(deps.storage, &env)?.ok_or(ContractError::AuctionNotSettled {})?;
+        let mut progress = AUCTION_PROGRESS.load(deps.storage)?;
+        if !progress.released {
+            amount += (clearing_price - auction.floor_price) * Uint128::from(progress.sales);
+            progress.released = true;
+            AUCTION_PROGRESS.save(deps.storage, &progress)?;
+        }
+    }
+
+    let denom = MINT_PRICE.load(deps.storage)?.denom;
+    decrease_total_deposits(deps.storage, &denom, amount)?;
+
+    let recipient = SALE_FUNDS_RECIPIENT.load(deps.storage)?;
+    let mut res = Response::default()
+        .add_attribute("action", "release_proceeds")
+        .add_attribute("recipient", &recipient)
+        .add_attribute("amount", amount);
+    if !amount.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin { denom, amount }],
+        });
+    }
+    Ok(res)
+}
+
+/// Counts an NFT sold, respecting the max NFT supply
+fn count_sale(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let sold = TOKENS_SOLD.update(storage, |sold| Ok::<u64, StdError>(sold + 1))?;
+    if let Some(max_supply) = MAX_NFT_SUPPLY.may_load(storage)? {
+        if sold > max_supply {
+            return Err(ContractError::MintedOut {});
+        }
+    }
+    Ok(())
+}
+
+/// Counts an NFT created by splitting, respecting the split allowance
+fn count_split(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let splits = SPLITS.update(storage, |splits| Ok::<u64, StdError>(splits + 1))?;
+    if let Some(max) = MAX_SPLITS.may_load(storage)? {
+        if splits > max {
+            return Err(ContractError::SplitLimitReached { max });
+        }
+    }
+    Ok(())
+}
+
+/// Mints the next NFT to `owner`, guarded by `guardian` if given.
+/// Sales and splits are counted by the caller.
+pub fn mint_token(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    guardian: Option<&Addr>,
+) -> Result<String, ContractError> {
+    // Load previous_token_id, incrementing it, saving the new value, and returning the result
+    let next_token_id =
+        PREVIOUS_TOKEN_ID.update(storage, |previous_id| Ok::<u64, StdError>(previous_id + 1))?;
+
+    // Formats the NFT token_uri (which links to the metadata) based on the token_id and the initial
+    // state for the dynamic NFT (in this case NFT trees start out as seedlings)
+    let token_id = next_token_id.to_string();
+    let token = TokenInfo {
+        owner: owner.clone(),
+        approvals: vec![],
+        token_uri: Some(stage_token_uri(storage, &token_id, Uint128::zero())?),
+        extension: MetadataExt {},
+    };
+    let base = Cw721Contract::default();
+    base.tokens.update(storage, &token_id, |old| match old {
+        Some(_) => Err(BaseContractError::Claimed {}),
+        None => Ok(token),
+    })?;
+    base.increment_tokens(storage)?;
+
+    // New NFTs only share in penalties from piggy banks broken after they were minted
+    let penalty_index = PENALTY_INDEX.may_load(storage)?.unwrap_or_default();
+    TOKEN_PENALTY_INDEX.save(storage, &token_id, &penalty_index)?;
+
+    if let Some(guardian) = guardian {
+        GUARDIANS.save(storage, &token_id, guardian)?;
+    }
+
+    Ok(token_id)
+}
+
+/// Carves `amount` out of an NFT into a newly minted NFT for `recipient`,
+/// only callable by the owner. The new NFT inherits the lock and guardian of the original.
+pub fn execute_split(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+    amount: Uint128,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let base = Cw721Contract::default();
+
+    if base.tokens.load(deps.storage, &token_id)?.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let recipient = deps.api.addr_validate(&recipient)?;
+    // Soulbound value can't be handed over by splitting it off either
+    if recipient != info.sender && SOULBOUND.load(deps.storage)? {
+        return Err(ContractError::Soulbound {});
+    }
+
+    // Take the amount out of the original NFT, the new NFT has to start
+    // with at least a minimum deposit
+    let balance = settle_token(deps.storage, &token_id)?;
+    if amount.is_zero() || amount > balance {
+        return Err(ContractError::InvalidSplit {});
+    }
+    let (min_deposit, _) = deposit_limits(deps.storage, &token_id)?;
+    if let Some(min_deposit) = min_deposit {
+        if amount < min_deposit {
+            return Err(ContractError::DepositTooSmall { min: min_deposit });
+        }
+    }
+    debit_token(deps.storage, &token_id, amount)?;
+    LAST_WITHDRAWALS.save(deps.storage, &token_id, &env.block.time)?;
+    record_activity(deps.storage, &env, &token_id, &info.sender)?;
+
+    // Mint the new NFT pre-funded with the amount, it isn't sold so it only
+    // uses up the split allowance
+    count_split(deps.storage)?;
+    let guardian = GUARDIANS.may_load(deps.storage, &token_id)?;
+    let new_token_id = mint_token(deps.storage, &recipient, guardian.as_ref())?;
+    credit_token(deps.storage, &new_token_id, amount)?;
+    record_contribution(deps.storage, &new_token_id, &info.sender, amount)?;
+    if let Some(until) = LOCKS.may_load(deps.storage, &token_id)? {
+        LOCKS.save(deps.storage, &new_token_id, &until)?;
+    }
+    if let Some(locked_at) = LOCK_STARTS.may_load(deps.storage, &token_id)? {
+        LOCK_STARTS.save(deps.storage, &new_token_id, &locked_at)?;
+    }
+
+    Ok(Response::default()
+        .add_attribute("action", "split")
+        .add_attribute("token_id", token_id)
+        .add_attribute("new_token_id", new_token_id)
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", amount))
+}
+
+pub fn execute_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    record_activity(deps.storage, &env, &token_id, &info.sender)?;
+    let amount = deposit_funds(deps, &env, &info, &token_id, &info.sender)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "deposit")
+        .add_attribute("value", amount.to_string())
+        .add_attribute("contributor", info.sender))
+}
+
+/// Deposits funds relayed by a trusted contract, attributing them to the end user
+pub fn execute_deposit_for(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+    contributor: String,
+    beneficiary_note: Option<String>,
+) -> Result<Response, ContractError> {
+    // Only trusted relayers can attribute deposits to someone else
+    if !RELAYERS.has(deps.storage, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let contributor = deps.api.addr_validate(&contributor)?;
+
+    let amount = deposit_funds(deps, &env, &info, &token_id, &contributor)?;
+
+    let mut res = Response::default()
+        .add_attribute("action", "deposit")
+        .add_attribute("value", amount.to_string())
+        .add_attribute("contributor", contributor)
+        .add_attribute("relayer", info.sender);
+    if let Some(beneficiary_note) = beneficiary_note {
+        res = res.add_attribute("beneficiary_note", beneficiary_note);
+    }
+    Ok(res)
+}
+
+pub fn execute_update_relayers(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::ConfigAdmin)?;
+
+    for relayer in add {
+        let relayer = deps.api.addr_validate(&relayer)?;
+        RELAYERS.save(deps.storage, &relayer, &Empty {})?;
+    }
+    for relayer in remove {
+        let relayer = deps.api.addr_validate(&relayer)?;
+        RELAYERS.remove(deps.storage, &relayer);
+    }
+
+    Ok(Response::default().add_attribute("action", "update_relayers"))
+}
+
+/// Checks the funds sent are a valid deposit, credits them to the token
+/// and records who they came from
+fn deposit_funds(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    token_id: &str,
+    contributor: &Addr,
+) -> Result<Uint128, ContractError> {
+    // Check that funds were actually sent
+    let denom = DEPOSIT_DENOM.load(deps.storage)?;
+    // Check the right kind of funds were sent
+    let amount = must_pay(info, &denom)?;
+
+    // Check the deposit is within the collection and token limits
+    check_deposit(deps.storage, token_id, amount)?;
+
+    credit_token(deps.storage, token_id, amount)?;
+    restart_lock(deps.storage, env, token_id)?;
+    record_contribution(deps.storage, token_id, contributor, amount)?;
+    increase_total_deposits(deps.storage, &denom, amount)?;
+
+    Ok(amount)
+}
+
+/// Funds added to a locked NFT have to be locked for the minimum lock duration
+/// too before the withdrawal fee is waived, so the lock restarts from now.
+/// A matured lock is over, so it is cleared and the new funds are unlocked.
+fn restart_lock(storage: &mut dyn Storage, env: &Env, token_id: &str) -> StdResult<()> {
+    match LOCKS.may_load(storage, token_id)? {
+        Some(until) if env.block.time >= until => {
+            LOCKS.remove(storage, token_id);
+            LOCK_STARTS.remove(storage, token_id);
+        }
+        Some(_) => LOCK_STARTS.save(storage, token_id, &env.block.time)?,
+        None => {}
+    }
+    Ok(())
+}
+
+/// Adds to the total a contributor has deposited into a token
+pub fn record_contribution(
+    storage: &mut dyn Storage,
+    token_id: &str,
+    contributor: &Addr,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    CONTRIBUTIONS.update(storage, (token_id, contributor), |total| {
+        Ok(total.unwrap_or_default() + amount)
+    })
+}
+
+/// Checks the sender is the contract owner, the admin cw721-base also treats as the minter
+pub fn assert_owner(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    cw_ownable::assert_owner(deps.storage, sender).map_err(|_| ContractError::Unauthorized {})
+}
+
+/// Checks the sender is the contract owner or has been granted `role`
+pub fn assert_role(deps: Deps, sender: &Addr, role: Role) -> Result<(), ContractError> {
+    if assert_owner(deps, sender).is_ok() {
+        return Ok(());
+    }
+    match ROLES.may_load(deps.storage, sender)? {
+        Some(roles) if roles.contains(&role) => Ok(()),
+        _ => Err(ContractError::Unauthorized {}),
+    }
+}
+
+/// Grants or revokes a role, only callable by the contract owner
+pub fn execute_update_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+    role: Role,
+    granted: bool,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+
+    let address = deps.api.addr_validate(&address)?;
+    let mut roles = ROLES.may_load(deps.storage, &address)?.unwrap_or_default();
+    roles.retain(|r| *r != role);
+    if granted {
+        roles.push(role.clone());
+    }
+    if roles.is_empty() {
+        ROLES.remove(deps.storage, &address);
+    } else {
+        ROLES.save(deps.storage, &address, &roles)?;
+    }
+
+    Ok(Response::default()
+        .add_attribute("action", if granted { "grant_role" } else { "revoke_role" })
+        .add_attribute("address", address)
+        .add_attribute("role", format!("{role:?}")))
+}
+
+pub fn execute_set_min_deposit(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    token_id: String,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    // Only the owner can set a minimum deposit for their NFT
+    let token = Cw721Contract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+    if token.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match amount {
+        Some(amount) => {
+            if let Some(max_token_balance) = MAX_TOKEN_BALANCE.may_load(deps.storage)? {
+                if amount > max_token_balance {
+                    return Err(ContractError::InvalidDepositLimits {});
+                }
+            }
+            TOKEN_MIN_DEPOSITS.save(deps.storage, &token_id, &amount)?
+        }
+        None => TOKEN_MIN_DEPOSITS.remove(deps.storage, &token_id),
+    }
+
+    Ok(Response::default()
+        .add_attribute("action", "set_min_deposit")
+        .add_attribute("token_id", token_id)
+        .add_attribute(
+            "amount",
+            amount.map_or_else(|| "none".to_string(), |a| a.to_string()),
+        ))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_create_plan(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+    amount: Uint128,
+    interval: Duration,
+    tip: Uint128,
+) -> Result<Response, ContractError> {
+    // Only the owner can create a savings plan for their NFT
+    let token = Cw721Contract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+    if token.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Plans are pre-funded in the deposit denom
+    let denom = DEPOSIT_DENOM.load(deps.storage)?;
+    let funded = must_pay(&info, &denom)?;
+
+    if amount.is_zero() || interval == Duration::Height(0) || interval == Duration::Time(0) {
+        return Err(ContractError::InvalidPlan {});
+    }
+    // Each installment is a deposit, so it must respect the minimum deposit
+    let (min_deposit, _) = deposit_limits(deps.storage, &token_id)?;
+    if let Some(min_deposit) = min_deposit {
+        if amount < min_deposit {
+            return Err(ContractError::DepositTooSmall { min: min_deposit });
+        }
+    }
+    // Must be funded for at least one installment
+    if funded < amount.checked_add(tip)? {
+        return Err(ContractError::WrongAmount {});
+    }
+
+    let plan_id = PREVIOUS_PLAN_ID.update(deps.storage, |previous_id| {
+        Ok::<u64, StdError>(previous_id + 1)
+    })?;
+    let next_execution = interval.after(&env.block);
+    PLANS.save(
+        deps.storage,
+        plan_id,
+        &Plan {
+            token_id: token_id.clone(),
+            owner: info.sender,
+            amount,
+            tip,
+            interval,
+            next_execution,
+            remaining: funded,
+        },
+    )?;
+    schedule_plan(deps.storage, plan_id, &next_execution)?;
+    TOKEN_PLANS.save(deps.storage, (&token_id, plan_id), &Empty {})?;
+    increase_total_deposits(deps.storage, &denom, funded)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "create_plan")
+        .add_attribute("plan_id", plan_id.to_string())
+        .add_attribute("token_id", token_id)
+        .add_attribute("amount", amount)
+        .add_attribute("funded", funded))
+}
+
+pub fn execute_cancel_plan(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    plan_id: u64,
+) -> Result<Response, ContractError> {
+    let plan = PLANS.load(deps.storage, plan_id)?;
+    if plan.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    PLANS.remove(deps.storage, plan_id);
+    TOKEN_PLANS.remove(deps.storage, (&plan.token_id, plan_id));
+    unschedule_plan(deps.storage, plan_id, &plan.next_execution);
+
+    // Refund whatever hasn't been saved yet
+    let denom = DEPOSIT_DENOM.load(deps.storage)?;
+    let msgs = refund_plan(&plan, &denom);
+    decrease_total_deposits(deps.storage, &denom, plan.remaining)?;
+
+    Ok(Response::default()
+        .add_messages(msgs)
+        .add_attribute("action", "cancel_plan")
+        .add_attribute("plan_id", plan_id.to_string())
+        .add_attribute("refund", plan.remaining))
+}
+
+/// Executes due savings plans, paying the caller the tips of every executed plan.
+/// Permissionless so that a keeper bot can drive it.
+pub fn execute_crank(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let denom = DEPOSIT_DENOM.load(deps.storage)?;
+
+    let due = due_plans(deps.storage, &env, limit)?;
+
+    let mut tips = Uint128::zero();
+    let mut refunds = Uint128::zero();
+    let mut msgs = vec![];
+    let mut executed = 0u32;
+    let mut closed = 0u32;
+    for plan_id in due {
+        let mut plan = PLANS.load(deps.storage, plan_id)?;
+        unschedule_plan(deps.storage, plan_id, &plan.next_execution);
+
+        // Plans that can't be credited (burned token or balance cap reached) are closed
+        if credit_token(deps.storage, &plan.token_id, plan.amount).is_err() {
+            PLANS.remove(deps.storage, plan_id);
+            TOKEN_PLANS.remove(deps.storage, (&plan.token_id, plan_id));
+            msgs.extend(refund_plan(&plan, &denom));
+            refunds += plan.remaining;
+            closed += 1;
+            continue;
+        }
+        restart_lock(deps.storage, &env, &plan.token_id)?;
+        record_contribution(deps.storage, &plan.token_id, &plan.owner, plan.amount)?;
+        tips += plan.tip;
+        plan.remaining -= plan.amount + plan.tip;
+        plan.next_execution = plan.interval.after(&env.block);
+        executed += 1;
+
+        // Close the plan once it can't fund another installment
+        if plan.remaining < plan.amount + plan.tip {
+            PLANS.remove(deps.storage, plan_id);
+            TOKEN_PLANS.remove(deps.storage, (&plan.token_id, plan_id));
+            msgs.extend(refund_plan(&plan, &denom));
+            refunds += plan.remaining;
+            closed += 1;
+        } else {
+            PLANS.save(deps.storage, plan_id, &plan)?;
+            schedule_plan(deps.storage, plan_id, &plan.next_execution)?;
+        }
+    }
+
+    decrease_total_deposits(deps.storage, &denom, tips + refunds)?;
+
+    if !tips.is_zero() {
+        msgs.push(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom,
+                amount: tips,
+            }],
+        });
+    }
+
+    Ok(Response::default()
+        .add_messages(msgs)
+        .add_attribute("action", "crank")
+        .add_attribute("executed", executed.to_string())
+        .add_attribute("closed", closed.to_string())
+        .add_attribute("tips", tips))
+}
+
+/// Queues a plan to run once `next_execution` expires
+fn schedule_plan(
+    storage: &mut dyn Storage,
+    plan_id: u64,
+    next_execution: &Expiration,
+) -> StdResult<()> {
+    match next_execution {
+        Expiration::AtHeight(height) => {
+            PLANS_DUE_AT_HEIGHT.save(storage, (*height, plan_id), &Empty {})
+        }
+        Expiration::AtTime(time) => {
+            PLANS_DUE_AT_TIME.save(storage, (time.nanos(), plan_id), &Empty {})
+        }
+        Expiration::Never {} => Ok(()),
+    }
+}
+
+/// Removes a plan from the queue it is waiting in
+fn unschedule_plan(storage: &mut dyn Storage, plan_id: u64, next_execution: &Expiration) {
+    match next_execution {
+        Expiration::AtHeight(height) => PLANS_DUE_AT_HEIGHT.remove(storage, (*height, plan_id)),
+        Expiration::AtTime(time) => PLANS_DUE_AT_TIME.remove(storage, (time.nanos(), plan_id)),
+        Expiration::Never {} => {}
+    }
+}
+
+/// Returns up to `limit` due plans, only reading the queues up to the current block
+fn due_plans(storage: &dyn Storage, env: &Env, limit: usize) -> StdResult<Vec<u64>> {
+    let mut due = PLANS_DUE_AT_HEIGHT
+        .keys(
+            storage,
+            None,
+            Some(Bound::inclusive((env.block.height, u64::MAX))),
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|key| key.map(|(_, plan_id)| plan_id))
+        .collect::<StdResult<Vec<_>>>()?;
+    let by_time = PLANS_DUE_AT_TIME
+        .keys(
+            storage,
+            None,
+            Some(Bound::inclusive((env.block.time.nanos(), u64::MAX))),
+            Order::Ascending,
+        )
+        .take(limit - due.len())
+        .map(|key| key.map(|(_, plan_id)| plan_id))
+        .collect::<StdResult<Vec<_>>>()?;
+    due.extend(by_time);
+    Ok(due)
+}
+
+/// Returns the message refunding the unspent funds of a plan to its owner
+fn refund_plan(plan: &Plan, denom: &str) -> Vec<BankMsg> {
+    if plan.remaining.is_zero() {
+        return vec![];
+    }
+    vec![BankMsg::Send {
+        to_address: plan.owner.to_string(),
+        amount: vec![Coin {
+            denom: denom.to_string(),
+            amount: plan.remaining,
+        }],
+    }]
+}
+
+/// Records funds coming into the contract that are owed to NFTs or savings plans
+fn increase_total_deposits(
+    storage: &mut dyn Storage,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    TOTAL_DEPOSITS.update(storage, denom, |total| -> StdResult<_> {
+        Ok(total.unwrap_or_default().checked_add(amount)?)
+    })
+}
+
+/// Records owed funds leaving the contract
+fn decrease_total_deposits(
+    storage: &mut dyn Storage,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    TOTAL_DEPOSITS.update(storage, denom, |total| -> StdResult<_> {
+        Ok(total.unwrap_or_default().checked_sub(amount)?)
+    })
+}
+
+/// Compares what the contract owes in a denom to what it holds
+fn denom_solvency(deps: Deps, env: &Env, denom: String) -> StdResult<DenomSolvency> {
+    let owed = TOTAL_DEPOSITS
+        .may_load(deps.storage, &denom)?
+        .unwrap_or_default();
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &denom)?
+        .amount;
+    Ok(DenomSolvency {
+        denom,
+        owed,
+        balance,
+        surplus: balance.saturating_sub(owed),
+        shortfall: owed.saturating_sub(balance),
+    })
+}
+
+/// Sends funds held beyond what is owed, only callable by a treasury manager
+pub fn execute_sweep(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    amount: Option<Uint128>,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::TreasuryManager)?;
+    let recipient = deps.api.addr_validate(&recipient)?;
+
+    let surplus = denom_solvency(deps.as_ref(), &env, denom.clone())?.surplus;
+    let amount = amount.unwrap_or(surplus);
+    if amount.is_zero() || amount > surplus {
+        return Err(ContractError::InsufficientSurplus { surplus });
+    }
+
+    Ok(Response::default()
+        .add_message(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin {
+                denom: denom.clone(),
+                amount,
+            }],
+        })
+        .add_attribute("action", "sweep")
+        .add_attribute("denom", denom)
+        .add_attribute("amount", amount)
+        .add_attribute("recipient", recipient))
+}
+
+/// Credits surplus funds in the deposit denom to an NFT, only callable by a
+/// treasury manager. Used for funds sent without a deposit message.
+pub fn execute_credit_unassigned(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::TreasuryManager)?;
+    Cw721Contract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+
+    let denom = DEPOSIT_DENOM.load(deps.storage)?;
+    let surplus = denom_solvency(deps.as_ref(), &env, denom.clone())?.surplus;
+    if amount.is_zero() || amount > surplus {
+        return Err(ContractError::InsufficientSurplus { surplus });
+    }
+
+    let balance = credit_token(deps.storage, &token_id, amount)?;
+    restart_lock(deps.storage, &env, &token_id)?;
+    increase_total_deposits(deps.storage, &denom, amount)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "credit_unassigned")
+        .add_attribute("token_id", token_id)
+        .add_attribute("amount", amount)
+        .add_attribute("balance", balance)
+        .add_attribute("admin", info.sender))
+}
+
+/// Returns the effective (min_deposit, max_token_balance) for a token
+pub fn deposit_limits(
+    storage: &dyn Storage,
+    token_id: &str,
+) -> StdResult<(Option<Uint128>, Option<Uint128>)> {
+    let min_deposit = MIN_DEPOSIT.may_load(storage)?;
+    let token_min_deposit = TOKEN_MIN_DEPOSITS.may_load(storage, token_id)?;
+    let min_deposit = match (min_deposit, token_min_deposit) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    };
+
+    Ok((min_deposit, MAX_TOKEN_BALANCE.may_load(storage)?))
+}
+
+/// Returns the metadata file name for the growth stage of a balance
+pub fn growth_stage(balance: Uint128) -> &'static str {
+    // Native token micro units are typically 6 decimal places
+    if balance > Uint128::new(100000000) {
+        "fullgrown.json"
+    } else if balance > Uint128::new(10000000) {
+        "tree.json"
+    } else if balance > Uint128::new(1000000) {
+        "sapling.json"
+    } else {
+        "seedling.json"
+    }
+}
+
+/// Adds funds to the balance of an NFT, enforcing the maximum token balance
+/// and updating the token_uri to match the new growth stage
+pub fn credit_token(
+    storage: &mut dyn Storage,
+    token_id: &str,
+    amount: Uint128,
+) -> Result<Uint128, ContractError> {
+    let base = Cw721Contract::default();
+
+    // Check that the token exists
+    let mut token = base.tokens.load(storage, token_id)?;
+
+    let new_balance = settle_token(storage, token_id)? + amount;
+    check_max_token_balance(storage, new_balance)?;
+    BALANCES.save(storage, token_id, &new_balance)?;
+
+    // Update the token_uri to the new growth stage
+    token.token_uri = Some(stage_token_uri(storage, token_id, new_balance)?);
+    base.tokens.save(storage, token_id, &token)?;
+
+    Ok(new_balance)
+}
+
+/// Takes funds out of the balance of an NFT, updating the token_uri to
+/// match the new growth stage
+pub fn debit_token(
+    storage: &mut dyn Storage,
+    token_id: &str,
+    amount: Uint128,
+) -> Result<Uint128, ContractError> {
+    let base = Cw721Contract::default();
+    let mut token = base.tokens.load(storage, token_id)?;
+
+    let new_balance = settle_token(storage, token_id)?.checked_sub(amount)?;
+    BALANCES.save(storage, token_id, &new_balance)?;
+
+    token.token_uri = Some(stage_token_uri(storage, token_id, new_balance)?);
+    base.tokens.save(storage, token_id, &token)?;
+
+    Ok(new_balance)
+}
+
+/// Checks a deposit is within the collection and token limits, returning the
+/// balance the NFT would have after it, without writing state
+pub fn check_deposit(
+    storage: &dyn Storage,
+    token_id: &str,
+    amount: Uint128,
+) -> Result<Uint128, ContractError> {
+    // Check that the token exists
+    Cw721Contract::default().tokens.load(storage, token_id)?;
+
+    let (min_deposit, _) = deposit_limits(storage, token_id)?;
+    if let Some(min_deposit) = min_deposit {
+        if amount < min_deposit {
+            return Err(ContractError::DepositTooSmall { min: min_deposit });
+        }
+    }
+
+    let new_balance = token_balance(storage, token_id)? + amount;
+    check_max_token_balance(storage, new_balance)?;
+    Ok(new_balance)
+}
+
+fn check_max_token_balance(storage: &dyn Storage, balance: Uint128) -> Result<(), ContractError> {
+    if let Some(max_token_balance) = MAX_TOKEN_BALANCE.may_load(storage)? {
+        if balance > max_token_balance {
+            return Err(ContractError::MaxBalanceExceeded {
+                max: max_token_balance,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Returns the token_uri for the growth stage of an NFT with the given balance
+pub fn stage_token_uri(
+    storage: &dyn Storage,
+    token_id: &str,
+    balance: Uint128,
+) -> StdResult<String> {
+    let base_url = BASE_URL.load(storage)?;
+    Ok(format!(
+        "{}/{}/{}",
+        base_url,
+        token_id,
+        growth_stage(balance)
+    ))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        // Optionally override a default cw721-base query
         // QueryMsg::Minter {} => unimplemented!(),
         QueryMsg::Extension { msg } => match msg {
             // Returns Coin type for the ballance of an NFT
             QueryExt::Balance { token_id } => to_binary(&Coin {
                 denom: DEPOSIT_DENOM.load(deps.storage)?,
-                amount: BALANCES
-                    .may_load(deps.storage, &token_id)?
-                    .unwrap_or_default(),
+                amount: token_balance(deps.storage, &token_id)?,
+            }),
+            QueryExt::Config {} => to_binary(&ConfigResponse {
+                deposit_denom: DEPOSIT_DENOM.load(deps.storage)?,
+                base_url: BASE_URL.load(deps.storage)?,
+                mint_price: MINT_PRICE.load(deps.storage)?,
+                max_nft_supply: MAX_NFT_SUPPLY.may_load(deps.storage)?,
+                max_splits: MAX_SPLITS.may_load(deps.storage)?,
+                sale_funds_recipient: SALE_FUNDS_RECIPIENT.load(deps.storage)?,
+                min_deposit: MIN_DEPOSIT.may_load(deps.storage)?,
+                max_token_balance: MAX_TOKEN_BALANCE.may_load(deps.storage)?,
+                fee: FEE_CONFIG.may_load(deps.storage)?,
+                early_break_penalty_bps: EARLY_BREAK_PENALTY.may_load(deps.storage)?,
+                require_expected_balance: REQUIRE_EXPECTED_BALANCE.load(deps.storage)?,
+                transfer_cooldown: TRANSFER_COOLDOWN.may_load(deps.storage)?,
+                soulbound: SOULBOUND.load(deps.storage)?,
+                allow_admin_recovery: ADMIN_RECOVERY.load(deps.storage)?,
+                guardian_timeout: GUARDIAN_TIMEOUT.may_load(deps.storage)?,
+                pause: PAUSE.load(deps.storage)?,
+                min_raise: MIN_RAISE.may_load(deps.storage)?,
+                dutch_auction: AUCTION.may_load(deps.storage)?,
+                bonding_curve: BONDING_CURVE.may_load(deps.storage)?,
+                payment_options: PAYMENT_OPTIONS.load(deps.storage)?,
+                mint_seed_bps: MINT_SEED.may_load(deps.storage)?,
+                max_mints_per_wallet: MAX_MINTS_PER_WALLET.may_load(deps.storage)?,
+            }),
+            QueryExt::Guardian { token_id } => to_binary(&GuardianResponse {
+                guardian: GUARDIANS.may_load(deps.storage, &token_id)?,
+                break_request: BREAK_REQUESTS.may_load(deps.storage, &token_id)?,
             }),
+            QueryExt::BreakRequests { start_after, limit } => {
+                let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+                let requests = BREAK_REQUESTS
+                    .range(
+                        deps.storage,
+                        start_after.as_deref().map(Bound::exclusive),
+                        None,
+                        Order::Ascending,
+                    )
+                    .filter(|item| match item {
+                        Ok((_, request)) => !request.approved,
+                        Err(_) => true,
+                    })
+                    .take(limit)
+                    .map(|item| {
+                        item.map(|(token_id, request)| BreakRequestResponse { token_id, request })
+                    })
+                    .collect::<StdResult<_>>()?;
+                to_binary(&BreakRequestsResponse { requests })
+            }
+            QueryExt::SimulateBurn { token_id } => {
+                // Check that the token exists
+                Cw721Contract::default()
+                    .tokens
+                    .load(deps.storage, &token_id)?;
+                let payout = burn_payout(deps.storage, &env, &token_id)
+                    .map_err(|err| StdError::generic_err(err.to_string()))?;
+                to_binary(&SimulateBurnResponse {
+                    payout: payout.payout,
+                    fee: payout.fee,
+                    penalty: payout.penalty,
+                    mint_refund: payout.mint_refund,
+                })
+            }
+            QueryExt::SimulateDeposit { token_id, amount } => {
+                let balance = check_deposit(deps.storage, &token_id, amount)
+                    .map_err(|err| StdError::generic_err(err.to_string()))?;
+                to_binary(&SimulateDepositResponse {
+                    balance: Coin {
+                        denom: DEPOSIT_DENOM.load(deps.storage)?,
+                        amount: balance,
+                    },
+                    stage: growth_stage(balance).trim_end_matches(".json").to_string(),
+                    token_uri: stage_token_uri(deps.storage, &token_id, balance)?,
+                })
+            }
+            QueryExt::Lock { token_id } => to_binary(&LockResponse {
+                until: LOCKS.may_load(deps.storage, &token_id)?,
+            }),
+            QueryExt::DepositLimits { token_id } => {
+                let (min_deposit, max_token_balance) = deposit_limits(deps.storage, &token_id)?;
+                to_binary(&DepositLimitsResponse {
+                    min_deposit,
+                    max_token_balance,
+                })
+            }
+            QueryExt::Plan { plan_id } => to_binary(&PlanResponse {
+                id: plan_id,
+                plan: PLANS.load(deps.storage, plan_id)?,
+            }),
+            QueryExt::Plans { start_after, limit } => {
+                let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+                let plans = PLANS
+                    .range(
+                        deps.storage,
+                        start_after.map(Bound::exclusive),
+                        None,
+                        Order::Ascending,
+                    )
+                    .take(limit)
+                    .map(|item| item.map(|(id, plan)| PlanResponse { id, plan }))
+                    .collect::<StdResult<_>>()?;
+                to_binary(&PlansResponse { plans })
+            }
+            QueryExt::Contributions {
+                token_id,
+                start_after,
+                limit,
+            } => {
+                let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+                let start_after = start_after
+                    .map(|addr| deps.api.addr_validate(&addr))
+                    .transpose()?;
+                let contributions = CONTRIBUTIONS
+                    .prefix(&token_id)
+                    .range(
+                        deps.storage,
+                        start_after.as_ref().map(Bound::exclusive),
+                        None,
+                        Order::Ascending,
+                    )
+                    .take(limit)
+                    .map(|item| {
+                        item.map(|(contributor, amount)| Contribution {
+                            contributor,
+                            amount,
+                        })
+                    })
+                    .collect::<StdResult<_>>()?;
+                to_binary(&ContributionsResponse { contributions })
+            }
+            QueryExt::Recovery { token_id } => {
+                let token = Cw721Contract::default()
+                    .tokens
+                    .load(deps.storage, &token_id)?;
+                let votes = RECOVERY_VOTES
+                    .prefix(&token_id)
+                    .range(deps.storage, None, None, Order::Ascending)
+                    .map(|item| item.map(|(signer, new_owner)| RecoveryVote { signer, new_owner }))
+                    .collect::<StdResult<_>>()?;
+                to_binary(&RecoveryResponse {
+                    config: recovery_config(deps.storage, &token_id, &token.owner)?,
+                    votes,
+                    pending: PENDING_RECOVERIES.may_load(deps.storage, &token_id)?,
+                })
+            }
+            QueryExt::Beneficiary { token_id } => {
+                let token = Cw721Contract::default()
+                    .tokens
+                    .load(deps.storage, &token_id)?;
+                let beneficiary = beneficiary(deps.storage, &token_id, &token.owner)?;
+                let claimable_at = beneficiary
+                    .as_ref()
+                    .map(|beneficiary| {
+                        inheritance_claimable_at(deps.storage, &token_id, beneficiary)
+                    })
+                    .transpose()?;
+                to_binary(&BeneficiaryResponse {
+                    beneficiary,
+                    last_activity: LAST_ACTIVITY.may_load(deps.storage, &token_id)?,
+                    claimable_at,
+                })
+            }
+            QueryExt::Roles { address } => {
+                let address = deps.api.addr_validate(&address)?;
+                to_binary(&RolesResponse {
+                    roles: ROLES.may_load(deps.storage, &address)?.unwrap_or_default(),
+                })
+            }
+            QueryExt::Solvency {} => {
+                let denoms = TOTAL_DEPOSITS
+                    .keys(deps.storage, None, None, Order::Ascending)
+                    .collect::<StdResult<Vec<_>>>()?
+                    .into_iter()
+                    .map(|denom| denom_solvency(deps, &env, denom))
+                    .collect::<StdResult<_>>()?;
+                to_binary(&SolvencyResponse { denoms })
+            }
+            QueryExt::CurrentPrice {} => to_binary(&current_price(deps.storage, &env)?),
+            QueryExt::PaymentOptions {} => {
+                let price = current_price(deps.storage, &env)?;
+                let mut options = vec![PaymentOption::Native {
+                    denom: price.denom,
+                    amount: price.amount,
+                }];
+                options.extend(PAYMENT_OPTIONS.load(deps.storage)?);
+                to_binary(&PaymentOptionsResponse { options })
+            }
+            QueryExt::PriceAt { token_number } => to_binary(&price_at(deps.storage, token_number)?),
+            QueryExt::Raise {} => to_binary(&RaiseResponse {
+                min_raise: MIN_RAISE.may_load(deps.storage)?,
+                status: raise_status(deps.storage, &env)?,
+                minted: RAISE_MINTED.may_load(deps.storage)?.unwrap_or_default(),
+                escrowed: Coin {
+                    denom: MINT_PRICE.load(deps.storage)?.denom,
+                    amount: ESCROWED_PROCEEDS
+                        .may_load(deps.storage)?
+                        .unwrap_or_default(),
+                },
+            }),
+            QueryExt::Relayers { start_after, limit } => {
+                let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+                let start_after = start_after
+                    .map(|addr| deps.api.addr_validate(&addr))
+                    .transpose()?;
+                let relayers = RELAYERS
+                    .keys(
+                        deps.storage,
+                        start_after.as_ref().map(Bound::exclusive),
+                        None,
+                        Order::Ascending,
+                    )
+                    .take(limit)
+                    .collect::<StdResult<_>>()?;
+                to_binary(&RelayersResponse { relayers })
+            }
         },
 
         // Use default cw721-base query implementation
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{OverflowError, StdError, Timestamp, Uint128};
 use cw_utils::PaymentError;
 use thiserror::Error;
 
@@ -11,6 +11,9 @@ pub enum ContractError {
     #[error("{0}")]
     PaymentError(#[from] PaymentError),
 
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
     #[error("The collection has fully minted out")]
     MintedOut {},
 
@@ -20,6 +23,110 @@ pub enum ContractError {
     #[error("Wrong amount was sent")]
     WrongAmount {},
 
+    #[error("Deposit is below the minimum of {min}")]
+    DepositTooSmall { min: Uint128 },
+
+    #[error("Deposit would take the balance above the maximum of {max}")]
+    MaxBalanceExceeded { max: Uint128 },
+
+    #[error("Minimum deposit can't be greater than the maximum token balance")]
+    InvalidDepositLimits {},
+
+    #[error("Savings plans need a non-zero amount and interval")]
+    InvalidPlan {},
+
+    #[error("The NFT is locked until {until}")]
+    Locked { until: Timestamp },
+
+    #[error("Locks must be in the future and can only be extended")]
+    InvalidLock {},
+
+    #[error("Can't merge an NFT into itself")]
+    InvalidMerge {},
+
+    #[error("Split amount must be non-zero and no more than the balance")]
+    InvalidSplit {},
+
+    #[error("No more than {max} NFTs can be created by splitting")]
+    SplitLimitReached { max: u64 },
+
+    #[error("Expected a balance of {expected} but it is {actual}")]
+    BalanceMismatch { expected: Uint128, actual: Uint128 },
+
+    #[error("NFTs holding a balance must be transferred with an expected balance")]
+    ExpectedBalanceRequired {},
+
+    #[error("The NFT can't be transferred until {until}")]
+    TransferCooldown { until: Timestamp },
+
+    #[error("NFTs in this collection are soulbound and can't be transferred")]
+    Soulbound {},
+
+    #[error("Admin recovery can only be allowed for soulbound collections")]
+    InvalidAdminRecovery {},
+
+    #[error("The guardian must approve breaking open this NFT")]
+    GuardianApprovalRequired {},
+
+    #[error("The NFT has no guardian")]
+    NoGuardian {},
+
+    #[error("Recovery needs a threshold between 1 and the number of distinct addresses")]
+    InvalidRecovery {},
+
+    #[error("The recovery can't complete until {until}")]
+    RecoveryDelay { until: Timestamp },
+
+    #[error("The beneficiary can't claim the NFT until {until}")]
+    InheritanceNotClaimable { until: Timestamp },
+
+    #[error("This action is paused")]
+    Paused {},
+
+    #[error("Withdrawals can't be paused again until {until}")]
+    WithdrawPauseCooldown { until: Timestamp },
+
+    #[error("Only the surplus of {surplus} can be swept")]
+    InsufficientSurplus { surplus: Uint128 },
+
+    #[error("The minimum raise failed, no more NFTs can be minted")]
+    RaiseFailed {},
+
+    #[error("The minimum raise hasn't succeeded yet")]
+    RaiseNotSucceeded {},
+
+    #[error(
+        "NFTs with an escrowed mint payment can't be merged, or broken open before the raise ends"
+    )]
+    MintRefundable {},
+
+    #[error("Auction floor can't be above the start price, the decay interval must be non-zero, and rebates can't be combined with a minimum raise")]
+    InvalidAuction {},
+
+    #[error("The mint price can follow a Dutch auction or a bonding curve, not both")]
+    InvalidBondingCurve {},
+
+    #[error("Payment options need a non-zero price in a new denom or token, and can't be combined with a minimum raise, Dutch auction, bonding curve or mint seed")]
+    InvalidPaymentOptions {},
+
+    #[error("The mint seed can't be more than 10000 basis points, and needs the mint price in the deposit denom and no minimum raise")]
+    InvalidMintSeed {},
+
+    #[error("The auction hasn't settled yet")]
+    AuctionNotSettled {},
+
+    #[error("No rebate to claim")]
+    NoRebate {},
+
+    #[error("Wallets can't buy more than {max} NFTs")]
+    WalletLimitReached { max: u64 },
+
+    #[error("Fees and penalties can't be more than 10000 basis points")]
+    InvalidFee {},
+
+    #[error("The withdrawal fee can't be more than {max} basis points")]
+    FeeTooHigh { max: u64 },
+
     /// This inherits from cw721-base::ContractError to handle the base contract errors
     #[error("NFT contract error: {0}")]
     Cw721Error(#[from] cw721_base::ContractError),
@@ -1,5 +1,13 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Coin, CustomMsg, Empty, Uint128};
+use cosmwasm_std::{Addr, Binary, Coin, CustomMsg, Empty, StdError, StdResult, Timestamp, Uint128};
+use cw20::Cw20ReceiveMsg;
+use cw721_base::Action;
+use cw_utils::{Duration, Expiration};
+
+use crate::state::{
+    Beneficiary, BondingCurve, BreakRequest, DutchAuction, FeeConfig, MinRaise, Pause,
+    PaymentOption, PendingRecovery, Plan, RecoveryConfig, Role,
+};
 
 // Implements extended on-chain metadata, by default cw721 NFTs only store a
 // token_uri, which is a URL to off-chain metadata (same as ERC721).
@@ -12,12 +20,158 @@ pub struct MetadataExt {}
 #[cw_serde]
 pub enum ExecuteExt {
     /// Used to deposit funds in a particular NFT
-    Deposit {
+    Deposit { token_id: String },
+    /// Updates the token_uri of an NFT, only callable by a metadata updater
+    UpdateTokenUri { token_id: String, token_uri: String },
+    /// Sets a minimum deposit for a particular NFT, only callable by the owner.
+    /// The collection minimum still applies if it is higher.
+    /// Set to None to remove the per-token minimum.
+    SetMinDeposit {
+        token_id: String,
+        amount: Option<Uint128>,
+    },
+    /// Creates a savings plan that moves `amount` into an NFT every `interval`,
+    /// only callable by the owner. The funds sent pre-fund the plan, and each
+    /// execution pays `tip` to the account that cranked it.
+    CreatePlan {
+        token_id: String,
+        amount: Uint128,
+        interval: Duration,
+        tip: Uint128,
+    },
+    /// Cancels a savings plan, refunding the unspent funds to its owner
+    CancelPlan { plan_id: u64 },
+    /// Executes up to `limit` due savings plans, anyone can call this
+    Crank { limit: Option<u32> },
+    /// Used by trusted relayer contracts to deposit funds in a particular NFT
+    /// on behalf of `contributor`, the end user the deposit is recorded against
+    DepositFor {
+        token_id: String,
+        contributor: String,
+        beneficiary_note: Option<String>,
+    },
+    /// Adds or removes trusted relayer contracts, only callable by a config admin
+    UpdateRelayers {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Burns an NFT and pays out its balance to `recipient`, or the owner if None.
+    /// Operators can only pay out to the owner, unless the owner has opted in
+    /// with `AllowOperatorPayout`.
+    BreakOpen {
+        token_id: String,
+        recipient: Option<String>,
+    },
+    /// Lets operators choose the recipient when breaking open an NFT, only
+    /// callable by the owner
+    AllowOperatorPayout { token_id: String, allowed: bool },
+    /// Locks an NFT so it can't be broken open before `until`, only callable
+    /// by the owner. Existing locks can only be extended.
+    Lock { token_id: String, until: Timestamp },
+    /// Sets or removes the withdrawal fee, only callable by a treasury manager
+    UpdateFee { fee: Option<FeeMsg> },
+    /// Moves the balance, contributions, savings plans and lock of `source`
+    /// into `target` and burns `source`, only callable by the owner of both.
+    /// The later lock maturity wins.
+    Merge { source: String, target: String },
+    /// Moves `amount` out of an NFT into a newly minted NFT owned by `recipient`,
+    /// only callable by the owner. In soulbound collections `recipient` must be the owner.
+    /// Uses up the split allowance rather than the max NFT supply, and `amount`
+    /// must meet the minimum deposit.
+    Split {
+        token_id: String,
+        amount: Uint128,
+        recipient: String,
+    },
+    /// Same as TransferNft, but fails if the balance isn't `expected_balance`
+    /// so buyers can't be front-run by a withdrawal
+    TransferWithBalance {
+        recipient: String,
+        token_id: String,
+        expected_balance: Uint128,
+    },
+    /// Same as SendNft, but fails if the balance isn't `expected_balance`
+    SendWithBalance {
+        contract: String,
+        token_id: String,
+        msg: Binary,
+        expected_balance: Uint128,
+    },
+    /// Moves a soulbound NFT to a new owner, only callable by a config admin if
+    /// the collection allows admin recovery
+    RecoveryTransfer { token_id: String, recipient: String },
+    /// Sets the guardian who approves breaking open an NFT, or removes it if None.
+    /// The owner can set a guardian when there is none, after that only the
+    /// guardian can change it. A guardian can also be set when minting.
+    SetGuardian {
+        token_id: String,
+        guardian: Option<String>,
+    },
+    /// Asks the guardian for approval to break open an NFT, only callable by the owner
+    RequestBreak { token_id: String },
+    /// Approves a request to break open an NFT, only callable by the guardian
+    ApproveBreak { token_id: String },
+    /// Sets the addresses that can recover an NFT if its owner loses their keys,
+    /// or removes them if None, only callable by the owner. Clears any signatures.
+    SetRecovery {
         token_id: String,
+        recovery: Option<RecoveryMsg>,
     },
-    UpdateTokenUri {
+    /// Signs for moving an NFT to `new_owner`, only callable by its recovery addresses.
+    /// Once `threshold` addresses agree and the delay has passed, signing again
+    /// completes the recovery.
+    Recover { token_id: String, new_owner: String },
+    /// Cancels a recovery in progress, only callable by the owner
+    CancelRecovery { token_id: String },
+    /// Names who inherits an NFT if the owner stops interacting with it, or
+    /// removes them if None, only callable by the owner
+    SetBeneficiary {
         token_id: String,
-        token_uri: String,
+        beneficiary: Option<BeneficiaryMsg>,
+    },
+    /// Shows the owner is still active without depositing or withdrawing,
+    /// only callable by the owner
+    Ping { token_id: String },
+    /// Moves an NFT and its balance to the beneficiary once the owner has been
+    /// inactive for the inactivity period, only callable by the beneficiary
+    ClaimInheritance { token_id: String },
+    /// Pauses or resumes groups of actions in an emergency, only callable by a pauser.
+    /// Pausing withdrawals also holds back rebates and released mint proceeds.
+    /// Withdrawals resume on their own after 7 days, and can't be paused again
+    /// for another 7 days after that.
+    UpdatePause {
+        mint: bool,
+        deposit: bool,
+        withdraw: bool,
+        transfer: bool,
+    },
+    /// Grants a role to an account, only callable by the contract owner
+    GrantRole { address: String, role: Role },
+    /// Revokes a role from an account, only callable by the contract owner
+    RevokeRole { address: String, role: Role },
+    /// Sends funds the contract holds beyond what it owes to NFTs and savings plans,
+    /// such as accidental sends, only callable by a treasury manager.
+    /// Sweeps the whole surplus if `amount` is None.
+    Sweep {
+        denom: String,
+        amount: Option<Uint128>,
+        recipient: String,
+    },
+    /// Credits funds sent to the contract without a deposit message to an NFT,
+    /// only callable by a treasury manager. Can't credit more than the surplus.
+    CreditUnassigned { token_id: String, amount: Uint128 },
+    /// Sends the escrowed mint proceeds to the sale funds recipient once the
+    /// minimum raise succeeds or the Dutch auction settles, anyone can call this
+    ReleaseProceeds {},
+    /// Refunds what the sender paid above the clearing price once the Dutch
+    /// auction settles, if the auction offers rebates
+    ClaimRebate {},
+    /// Mints `count` NFTs in one transaction, paid for in the mint denom.
+    /// NFTs go to `recipient`, or the sender if None, guarded by `guardian` if given.
+    PurchaseBatch {
+        count: u64,
+        recipient: Option<String>,
+        guardian: Option<String>,
     },
 }
 impl CustomMsg for ExecuteExt {}
@@ -28,9 +182,241 @@ impl CustomMsg for ExecuteExt {}
 pub enum QueryExt {
     /// Query the current balance for an individual NFT
     Balance { token_id: String },
+    /// Query the collection configuration
+    Config {},
+    /// Query the deposit limits that apply to an individual NFT
+    DepositLimits { token_id: String },
+    /// Query a savings plan
+    Plan { plan_id: u64 },
+    /// Query all savings plans
+    Plans {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Query how much each account has contributed to an NFT
+    Contributions {
+        token_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Query the lock on an individual NFT
+    Lock { token_id: String },
+    /// Query what breaking open an NFT would pay out right now
+    SimulateBurn { token_id: String },
+    /// Query the balance and growth stage an NFT would have after a deposit,
+    /// errors if the deposit would be rejected
+    SimulateDeposit { token_id: String, amount: Uint128 },
+    /// Query the guardian of an NFT and any request to break it open
+    Guardian { token_id: String },
+    /// Query the requests to break open NFTs that are waiting for approval
+    BreakRequests {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Query the recovery setup of an NFT and any recovery in progress
+    Recovery { token_id: String },
+    /// Query the beneficiary of an NFT and when they can claim it
+    Beneficiary { token_id: String },
+    /// Query the roles granted to an account, the contract owner holds every
+    /// role without being granted it
+    Roles { address: String },
+    /// Query what the contract owes compared to what it holds, per denom
+    Solvency {},
+    /// Query the progress of the minimum raise
+    Raise {},
+    /// Query the price to mint an NFT right now
+    CurrentPrice {},
+    /// Query every accepted way to pay for a mint, starting with the mint price
+    PaymentOptions {},
+    /// Query the price to mint the nth NFT, counting from 1
+    PriceAt { token_number: u64 },
+    /// Query the trusted relayer contracts
+    Relayers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 impl CustomMsg for QueryExt {}
 
+#[cw_serde]
+pub struct ConfigResponse {
+    pub deposit_denom: String,
+    pub base_url: String,
+    pub mint_price: Coin,
+    pub max_nft_supply: Option<u64>,
+    pub max_splits: Option<u64>,
+    pub sale_funds_recipient: Addr,
+    pub min_deposit: Option<Uint128>,
+    pub max_token_balance: Option<Uint128>,
+    pub fee: Option<FeeConfig>,
+    pub early_break_penalty_bps: Option<u64>,
+    pub require_expected_balance: bool,
+    pub transfer_cooldown: Option<u64>,
+    pub soulbound: bool,
+    pub allow_admin_recovery: bool,
+    pub guardian_timeout: Option<u64>,
+    pub pause: Pause,
+    pub min_raise: Option<MinRaise>,
+    pub dutch_auction: Option<DutchAuction>,
+    pub bonding_curve: Option<BondingCurve>,
+    pub payment_options: Vec<PaymentOption>,
+    pub mint_seed_bps: Option<u64>,
+    pub max_mints_per_wallet: Option<u64>,
+}
+
+#[cw_serde]
+pub enum RaiseStatus {
+    /// Still selling towards the minimum before the deadline
+    Pending,
+    /// The minimum was sold, proceeds can be released
+    Succeeded,
+    /// The deadline passed without selling the minimum, NFTs can be burned for a refund
+    Failed,
+}
+
+#[cw_serde]
+pub struct RaiseResponse {
+    /// None if the collection has no minimum raise
+    pub min_raise: Option<MinRaise>,
+    pub status: Option<RaiseStatus>,
+    /// Number of NFTs sold towards the minimum
+    pub minted: u64,
+    /// Mint proceeds held by the contract
+    pub escrowed: Coin,
+}
+
+#[cw_serde]
+pub struct GuardianResponse {
+    pub guardian: Option<Addr>,
+    pub break_request: Option<BreakRequest>,
+}
+
+#[cw_serde]
+pub struct BreakRequestResponse {
+    pub token_id: String,
+    pub request: BreakRequest,
+}
+
+#[cw_serde]
+pub struct BreakRequestsResponse {
+    pub requests: Vec<BreakRequestResponse>,
+}
+
+#[cw_serde]
+pub struct RecoveryVote {
+    pub signer: Addr,
+    pub new_owner: Addr,
+}
+
+#[cw_serde]
+pub struct RecoveryResponse {
+    /// None if the NFT has no recovery set up by its current owner
+    pub config: Option<RecoveryConfig>,
+    pub votes: Vec<RecoveryVote>,
+    pub pending: Option<PendingRecovery>,
+}
+
+#[cw_serde]
+pub struct BeneficiaryResponse {
+    /// None if the NFT has no beneficiary named by its current owner
+    pub beneficiary: Option<Beneficiary>,
+    /// When the owner last deposited, withdrew or pinged
+    pub last_activity: Option<Timestamp>,
+    /// When the beneficiary can claim the NFT if the owner stays inactive
+    pub claimable_at: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct SimulateBurnResponse {
+    /// Amount paid to the recipient
+    pub payout: Uint128,
+    /// Amount paid to the treasury
+    pub fee: Uint128,
+    /// Amount forfeited to the other piggy banks for breaking a lock early
+    pub penalty: Uint128,
+    /// The escrowed mint payment refunded if the minimum raise failed
+    pub mint_refund: Option<Coin>,
+}
+
+#[cw_serde]
+pub struct SimulateDepositResponse {
+    /// The balance after the deposit
+    pub balance: Coin,
+    /// The growth stage after the deposit, e.g. "sapling"
+    pub stage: String,
+    /// The token_uri after the deposit
+    pub token_uri: String,
+}
+
+#[cw_serde]
+pub struct LockResponse {
+    pub until: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct PlanResponse {
+    pub id: u64,
+    pub plan: Plan,
+}
+
+#[cw_serde]
+pub struct PlansResponse {
+    pub plans: Vec<PlanResponse>,
+}
+
+#[cw_serde]
+pub struct Contribution {
+    pub contributor: Addr,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct ContributionsResponse {
+    pub contributions: Vec<Contribution>,
+}
+
+#[cw_serde]
+pub struct DenomSolvency {
+    pub denom: String,
+    /// Funds owed to NFTs and savings plans
+    pub owed: Uint128,
+    /// Funds held by the contract
+    pub balance: Uint128,
+    /// Funds held beyond what is owed, these can be swept
+    pub surplus: Uint128,
+    /// Funds owed beyond what is held, this should always be zero
+    pub shortfall: Uint128,
+}
+
+#[cw_serde]
+pub struct SolvencyResponse {
+    pub denoms: Vec<DenomSolvency>,
+}
+
+#[cw_serde]
+pub struct PaymentOptionsResponse {
+    pub options: Vec<PaymentOption>,
+}
+
+#[cw_serde]
+pub struct RolesResponse {
+    pub roles: Vec<Role>,
+}
+
+#[cw_serde]
+pub struct RelayersResponse {
+    pub relayers: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct DepositLimitsResponse {
+    /// The effective minimum for a single deposit, the higher of the
+    /// collection and per-token minimums
+    pub min_deposit: Option<Uint128>,
+    /// The maximum balance the NFT can hold
+    pub max_token_balance: Option<Uint128>,
+}
+
 // This contrains default cw721 logic with extensions.
 // If you don't need a particular extension, replace it with an
 // `Empty` type.
@@ -44,9 +430,10 @@ pub struct InstantiateMsg {
     /// Symbol of the NFT contract
     pub symbol: String,
 
-    /// The minter is the only one who can create new NFTs.
-    /// This is designed for a base NFT that is controlled by an external program
-    /// or contract. You will likely replace this with custom logic in custom NFTs
+    /// The minter owns the contract and is the admin for its configuration.
+    /// Ownership is handed over in two steps with UpdateOwnership, the new owner
+    /// must accept before an optional expiry, and can be renounced for good.
+    /// Query the current and pending owner with Ownership.
     pub minter: String,
 
     /// Allowed denoms for deposit
@@ -59,18 +446,264 @@ pub struct InstantiateMsg {
     /// The price to mint a new NFT
     pub mint_price: Coin,
 
-    /// Max NFT supply, the maximum number of NFTs that can be minted
-    /// If set None, unlimited NFTs can be minted
+    /// Max NFT supply, the maximum number of NFTs that can be sold.
+    /// NFTs created by splitting don't count towards it.
+    /// If set None, unlimited NFTs can be sold
     pub max_nft_supply: Option<u64>,
 
+    /// Max number of NFTs that can be created by splitting
+    /// If set None, NFTs can be split any number of times
+    pub max_splits: Option<u64>,
+
     /// The recipient for funds raised from the NFT mint
     pub sale_funds_recipient: String,
+
+    /// Minimum amount accepted by a single deposit, used to block dust deposits
+    /// If set None, any non-zero amount can be deposited
+    pub min_deposit: Option<Uint128>,
+
+    /// Maximum balance an individual NFT can hold
+    /// If set None, balances are uncapped
+    pub max_token_balance: Option<Uint128>,
+
+    /// Fee taken when an NFT is broken open, sent to a treasury
+    /// If set None, no fee is taken
+    pub fee: Option<FeeMsg>,
+
+    /// Share of the balance forfeited when a locked NFT is broken before it matures,
+    /// in basis points. The penalty is split evenly between the remaining NFTs.
+    /// If set None, locked NFTs can't be broken before they mature
+    pub early_break_penalty_bps: Option<u64>,
+
+    /// If true, NFTs holding a balance can only be transferred with
+    /// TransferWithBalance or SendWithBalance
+    #[serde(default)]
+    pub require_expected_balance: bool,
+
+    /// Seconds an NFT can't be transferred for after funds are withdrawn from it
+    /// If set None, there is no cooldown
+    pub transfer_cooldown: Option<u64>,
+
+    /// If true, NFTs are non-transferable, they can only be deposited into
+    /// and broken open by their owner
+    #[serde(default)]
+    pub soulbound: bool,
+
+    /// If true, a config admin can move soulbound NFTs to a new owner with
+    /// RecoveryTransfer. Only allowed if the collection is soulbound
+    #[serde(default)]
+    pub allow_admin_recovery: bool,
+
+    /// Seconds after which a request to break open a guarded NFT no longer
+    /// needs the guardian's approval
+    /// If set None, the guardian must always approve
+    pub guardian_timeout: Option<u64>,
+
+    /// Crowdfunding goal, mint proceeds are held by the contract until it is met
+    /// and refunded to holders who burn their NFTs if it isn't met by the deadline
+    /// If set None, mint proceeds go straight to the sale funds recipient
+    pub min_raise: Option<MinRaise>,
+
+    /// Dutch auction mint pricing in the mint price denom, the mint price amount
+    /// is not used. Overpayments are refunded.
+    /// If set None, NFTs are sold at the mint price
+    pub dutch_auction: Option<DutchAuctionMsg>,
+
+    /// Mint pricing in the mint price denom that rises with every NFT minted,
+    /// including NFTs minted by Split. The mint price amount is not used.
+    /// Overpayments are refunded. Can't be combined with a Dutch auction.
+    /// If set None, NFTs are sold at the mint price
+    pub bonding_curve: Option<BondingCurve>,
+
+    /// Other ways to pay for a mint, each at its own fixed price. Proceeds go to
+    /// the sale funds recipient in the denom or token paid. Can't be combined
+    /// with a minimum raise, Dutch auction, bonding curve or mint seed.
+    #[serde(default)]
+    pub payment_options: Vec<PaymentOptionMsg>,
+
+    /// Share of the mint proceeds deposited straight into the new NFT, in basis
+    /// points, so every NFT starts with a floor value. Only applies to mints paid
+    /// at the mint price, which must be in the deposit denom. Can't be combined
+    /// with a minimum raise or payment options.
+    /// If set None, all mint proceeds go to the sale funds recipient
+    pub mint_seed_bps: Option<u64>,
+
+    /// Max NFTs a single wallet can buy, counting batch purchases.
+    /// If set None, wallets can buy any number of NFTs
+    pub max_mints_per_wallet: Option<u64>,
+}
+
+#[cw_serde]
+pub enum PaymentOptionMsg {
+    /// Pay with a native or IBC denom
+    Native { denom: String, amount: Uint128 },
+    /// Pay by sending cw20 tokens to this contract with ReceiveMsg::Mint
+    Cw20 { address: String, amount: Uint128 },
+}
+
+#[cw_serde]
+pub struct DutchAuctionMsg {
+    /// The price when the contract is instantiated
+    pub start_price: Uint128,
+    /// The lowest the price goes
+    pub floor_price: Uint128,
+    /// Amount the price drops by every `decay_interval`
+    pub decay: Uint128,
+    pub decay_interval: Duration,
+    /// If true, the auction settles when the collection sells out or the price
+    /// reaches the floor, and buyers can claim back what they paid above the
+    /// price at that point. Can't be combined with a minimum raise.
+    pub rebate: bool,
 }
 
-// The execute message type for this contract.
+#[cw_serde]
+pub struct FeeMsg {
+    /// The fee in basis points of the balance, at most 1000
+    pub bps: u64,
+    /// The recipient of the fee
+    pub treasury: String,
+    /// If true, NFTs that were held locked until maturity pay no fee
+    pub waive_after_maturity: bool,
+    /// Minimum seconds an NFT must have been locked for to have the fee waived,
+    /// so short locks can't be used to skip the fee
+    pub min_lock_duration: u64,
+}
+
+#[cw_serde]
+pub struct RecoveryMsg {
+    /// The addresses that can sign for a recovery
+    pub addresses: Vec<String>,
+    /// Number of addresses that must agree on the new owner
+    pub threshold: u32,
+    /// Seconds the owner has to cancel once the threshold is reached
+    pub delay: u64,
+}
+
+#[cw_serde]
+pub struct BeneficiaryMsg {
+    /// The address that inherits the NFT
+    pub address: String,
+    /// Seconds of owner inactivity after which the beneficiary can claim the NFT
+    pub inactivity_period: u64,
+}
+
+// The cw721-base execute message type, used for messages handled by the base contract.
 // If you don't need the Metadata and Execute extensions, you can use the
 // `Empty` type.
-pub type ExecuteMsg = cw721_base::ExecuteMsg<MetadataExt, ExecuteExt>;
+pub type BaseExecuteMsg = cw721_base::ExecuteMsg<MetadataExt, ExecuteExt>;
+
+// The execute message type for this contract. These are the cw721-base messages
+// plus the cw20 Receive hook, which cw20 tokens call as a top-level message.
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Transfer is a base message to move a token to another account without triggering actions
+    TransferNft { recipient: String, token_id: String },
+    /// Send is a base message to transfer a token to a contract and trigger an action
+    /// on the receiving contract.
+    SendNft {
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    },
+    /// Allows operator to transfer / send the token from the owner's account.
+    /// If expiration is set, then this allowance has a time/height limit
+    Approve {
+        spender: String,
+        token_id: String,
+        expires: Option<Expiration>,
+    },
+    /// Remove previously granted Approval
+    Revoke { spender: String, token_id: String },
+    /// Allows operator to transfer / send any token from the owner's account.
+    /// If expiration is set, then this allowance has a time/height limit
+    ApproveAll {
+        operator: String,
+        expires: Option<Expiration>,
+    },
+    /// Remove previously granted ApproveAll permission
+    RevokeAll { operator: String },
+    /// Buys a new NFT for the sender, paid with the mint price or another
+    /// native payment option. The token_id and owner are ignored.
+    /// The NFT is guarded by `guardian` if given.
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: MetadataExt,
+        guardian: Option<String>,
+    },
+    /// Burn an NFT the sender has access to
+    Burn { token_id: String },
+    /// Extension msg
+    Extension { msg: ExecuteExt },
+    /// Update the contract's ownership
+    UpdateOwnership(Action),
+    /// Buys a new NFT with a cw20 payment option, called by the cw20 token
+    /// when tokens are sent to this contract with a ReceiveMsg
+    Receive(Cw20ReceiveMsg),
+}
+
+impl TryFrom<ExecuteMsg> for BaseExecuteMsg {
+    type Error = StdError;
+
+    fn try_from(msg: ExecuteMsg) -> StdResult<Self> {
+        Ok(match msg {
+            ExecuteMsg::TransferNft {
+                recipient,
+                token_id,
+            } => Self::TransferNft {
+                recipient,
+                token_id,
+            },
+            ExecuteMsg::SendNft {
+                contract,
+                token_id,
+                msg,
+            } => Self::SendNft {
+                contract,
+                token_id,
+                msg,
+            },
+            ExecuteMsg::Approve {
+                spender,
+                token_id,
+                expires,
+            } => Self::Approve {
+                spender,
+                token_id,
+                expires,
+            },
+            ExecuteMsg::Revoke { spender, token_id } => Self::Revoke { spender, token_id },
+            ExecuteMsg::ApproveAll { operator, expires } => Self::ApproveAll { operator, expires },
+            ExecuteMsg::RevokeAll { operator } => Self::RevokeAll { operator },
+            ExecuteMsg::Mint {
+                token_id,
+                owner,
+                token_uri,
+                extension,
+                ..
+            } => Self::Mint {
+                token_id,
+                owner,
+                token_uri,
+                extension,
+            },
+            ExecuteMsg::Burn { token_id } => Self::Burn { token_id },
+            ExecuteMsg::Extension { msg } => Self::Extension { msg },
+            ExecuteMsg::UpdateOwnership(action) => Self::UpdateOwnership(action),
+            ExecuteMsg::Receive(_) => {
+                return Err(StdError::generic_err("Receive is not a cw721-base message"))
+            }
+        })
+    }
+}
+
+/// The message cw20 tokens forward to this contract in a Receive
+#[cw_serde]
+pub enum ReceiveMsg {
+    /// Buys a new NFT for the sender of the tokens, guarded by `guardian` if given
+    Mint { guardian: Option<String> },
+}
 
 // The query message type for this contract.
 // If you don't need the QueryExt extension, you can use the
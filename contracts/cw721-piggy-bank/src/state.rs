@@ -1,5 +1,7 @@
-use cosmwasm_std::{Addr, Coin, Uint128};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Coin, Decimal, Empty, Timestamp, Uint128};
 use cw_storage_plus::{Item, Map};
+use cw_utils::{Duration, Expiration};
 
 /// Map for storing NFT balances (token_id, amount)
 pub const BALANCES: Map<&str, Uint128> = Map::new("nft_balances");
@@ -16,8 +18,313 @@ pub const BASE_URL: Item<String> = Item::new("base_url");
 /// Previous token id, represent the last NFT token ID that was minted
 pub const PREVIOUS_TOKEN_ID: Item<u64> = Item::new("previous_token_id");
 
-/// The max number of NFTs that can be minted
+/// The max number of NFTs that can be sold
 pub const MAX_NFT_SUPPLY: Item<u64> = Item::new("max_nft_supply");
 
+/// Number of NFTs sold, this counts towards the max NFT supply and sets the
+/// bonding curve position. NFTs created by splitting aren't sold.
+pub const TOKENS_SOLD: Item<u64> = Item::new("tokens_sold");
+
+/// The max number of NFTs that can be created by splitting
+pub const MAX_SPLITS: Item<u64> = Item::new("max_splits");
+
+/// Number of NFTs created by splitting
+pub const SPLITS: Item<u64> = Item::new("splits");
+
 /// The recipient for funds from initial NFT sale
 pub const SALE_FUNDS_RECIPIENT: Item<Addr> = Item::new("sale_funds_recipient");
+
+/// Minimum amount accepted by a single deposit into any NFT
+pub const MIN_DEPOSIT: Item<Uint128> = Item::new("min_deposit");
+
+/// Maximum balance an individual NFT can hold
+pub const MAX_TOKEN_BALANCE: Item<Uint128> = Item::new("max_token_balance");
+
+/// Map for storing per-token minimum deposits set by NFT owners (token_id, amount)
+pub const TOKEN_MIN_DEPOSITS: Map<&str, Uint128> = Map::new("token_min_deposits");
+
+/// A recurring savings plan, pre-funded by the owner of an NFT
+#[cw_serde]
+pub struct Plan {
+    /// The NFT the plan saves into
+    pub token_id: String,
+    /// The account that funded the plan, refunded when it ends
+    pub owner: Addr,
+    /// Amount moved into the NFT balance on each execution
+    pub amount: Uint128,
+    /// Amount paid to whoever cranks the plan on each execution
+    pub tip: Uint128,
+    /// How often the plan executes, in blocks or seconds
+    pub interval: Duration,
+    /// When the plan can next be executed
+    pub next_execution: Expiration,
+    /// Funds left to pay for future installments and tips
+    pub remaining: Uint128,
+}
+
+/// Map for storing savings plans (plan_id, plan)
+pub const PLANS: Map<u64, Plan> = Map::new("plans");
+
+/// Plans saving into each NFT, keyed by (token_id, plan_id)
+pub const TOKEN_PLANS: Map<(&str, u64), Empty> = Map::new("token_plans");
+
+/// Plans waiting on a block height, keyed by (next execution height, plan_id)
+pub const PLANS_DUE_AT_HEIGHT: Map<(u64, u64), Empty> = Map::new("plans_due_at_height");
+
+/// Plans waiting on a time, keyed by (next execution in nanos, plan_id)
+pub const PLANS_DUE_AT_TIME: Map<(u64, u64), Empty> = Map::new("plans_due_at_time");
+
+/// Previous plan id, represents the last savings plan that was created
+pub const PREVIOUS_PLAN_ID: Item<u64> = Item::new("previous_plan_id");
+
+/// Map for storing how much each account has contributed to an NFT ((token_id, contributor), amount)
+pub const CONTRIBUTIONS: Map<(&str, &Addr), Uint128> = Map::new("contributions");
+
+/// Trusted relayer contracts allowed to deposit on behalf of their users
+pub const RELAYERS: Map<&Addr, Empty> = Map::new("relayers");
+
+/// Owners that let operators choose the payout recipient of their NFT (token_id, owner)
+pub const OPERATOR_PAYOUT_OPT_INS: Map<&str, Addr> = Map::new("operator_payout_opt_ins");
+
+/// Fee taken from the balance of an NFT when it is broken open
+#[cw_serde]
+pub struct FeeConfig {
+    /// The fee in basis points of the balance
+    pub bps: u64,
+    /// The recipient of the fee
+    pub treasury: Addr,
+    /// If true, NFTs that were held locked until maturity pay no fee
+    pub waive_after_maturity: bool,
+    /// Minimum seconds an NFT must have been locked for to have the fee waived
+    pub min_lock_duration: u64,
+}
+
+/// The withdrawal fee, if any
+pub const FEE_CONFIG: Item<FeeConfig> = Item::new("fee_config");
+
+/// Map for storing the time until which an NFT can't be broken open (token_id, until)
+pub const LOCKS: Map<&str, Timestamp> = Map::new("locks");
+
+/// Map for storing when the lock on an NFT started (token_id, locked_at)
+pub const LOCK_STARTS: Map<&str, Timestamp> = Map::new("lock_starts");
+
+/// Share of the balance forfeited when a locked NFT is broken early, in basis points
+pub const EARLY_BREAK_PENALTY: Item<u64> = Item::new("early_break_penalty");
+
+/// Total early-break penalties shared out per NFT since the contract was instantiated
+pub const PENALTY_INDEX: Item<Decimal> = Item::new("penalty_index");
+
+/// Map for storing the penalty index an NFT was last credited at (token_id, index)
+pub const TOKEN_PENALTY_INDEX: Map<&str, Decimal> = Map::new("token_penalty_index");
+
+/// If true, funded NFTs can only be transferred by declaring their expected balance
+pub const REQUIRE_EXPECTED_BALANCE: Item<bool> = Item::new("require_expected_balance");
+
+/// Seconds an NFT can't be transferred for after funds are withdrawn from it
+pub const TRANSFER_COOLDOWN: Item<u64> = Item::new("transfer_cooldown");
+
+/// Map for storing when funds were last withdrawn from an NFT (token_id, time)
+pub const LAST_WITHDRAWALS: Map<&str, Timestamp> = Map::new("last_withdrawals");
+
+/// If true, NFTs can't be transferred or approved for transfer
+pub const SOULBOUND: Item<bool> = Item::new("soulbound");
+
+/// If true, a config admin can move soulbound NFTs to a new owner
+pub const ADMIN_RECOVERY: Item<bool> = Item::new("admin_recovery");
+
+/// Map for storing the guardian who approves breaking open an NFT (token_id, guardian)
+pub const GUARDIANS: Map<&str, Addr> = Map::new("guardians");
+
+/// A request from the owner of a guarded NFT to break it open
+#[cw_serde]
+pub struct BreakRequest {
+    pub requested_at: Timestamp,
+    pub approved: bool,
+}
+
+/// Map for storing requests to break open guarded NFTs (token_id, request)
+pub const BREAK_REQUESTS: Map<&str, BreakRequest> = Map::new("break_requests");
+
+/// Seconds after which a break request no longer needs the guardian's approval
+pub const GUARDIAN_TIMEOUT: Item<u64> = Item::new("guardian_timeout");
+
+/// Addresses that can jointly move an NFT to a new owner if its owner loses their keys
+#[cw_serde]
+pub struct RecoveryConfig {
+    /// The owner who set up recovery, it only applies while they own the NFT
+    pub owner: Addr,
+    pub addresses: Vec<Addr>,
+    /// Number of addresses that must agree on the new owner
+    pub threshold: u32,
+    /// Seconds the owner has to cancel once the threshold is reached
+    pub delay: u64,
+}
+
+/// Map for storing the recovery setup of an NFT (token_id, config)
+pub const RECOVERY_CONFIGS: Map<&str, RecoveryConfig> = Map::new("recovery_configs");
+
+/// Map for storing the new owner each recovery address signed for ((token_id, signer), new_owner)
+pub const RECOVERY_VOTES: Map<(&str, &Addr), Addr> = Map::new("recovery_votes");
+
+/// A recovery that reached its threshold and is waiting out the delay
+#[cw_serde]
+pub struct PendingRecovery {
+    pub new_owner: Addr,
+    pub ready_at: Timestamp,
+}
+
+/// Map for storing recoveries waiting out their delay (token_id, recovery)
+pub const PENDING_RECOVERIES: Map<&str, PendingRecovery> = Map::new("pending_recoveries");
+
+/// Who inherits an NFT if its owner stops interacting with it
+#[cw_serde]
+pub struct Beneficiary {
+    /// The owner who named the beneficiary, it only applies while they own the NFT
+    pub owner: Addr,
+    pub address: Addr,
+    /// Seconds of owner inactivity after which the beneficiary can claim the NFT
+    pub inactivity_period: u64,
+}
+
+/// Map for storing the beneficiary of an NFT (token_id, beneficiary)
+pub const BENEFICIARIES: Map<&str, Beneficiary> = Map::new("beneficiaries");
+
+/// Map for storing when the owner last interacted with an NFT (token_id, time)
+pub const LAST_ACTIVITY: Map<&str, Timestamp> = Map::new("last_activity");
+
+/// Actions halted by the admin in an emergency
+#[cw_serde]
+#[derive(Default)]
+pub struct Pause {
+    pub mint: bool,
+    pub deposit: bool,
+    pub transfer: bool,
+    /// Withdrawals can only be paused for a limited time, so funds can't be frozen
+    pub withdraw_until: Option<Timestamp>,
+}
+
+/// The actions currently paused
+pub const PAUSE: Item<Pause> = Item::new("pause");
+
+/// Admin duties that the contract owner can delegate, the owner can always perform them
+#[cw_serde]
+pub enum Role {
+    /// Manages relayers and admin recovery transfers
+    ConfigAdmin,
+    /// Updates token metadata
+    MetadataUpdater,
+    /// Pauses and resumes actions in an emergency
+    Pauser,
+    /// Manages the withdrawal fee and treasury
+    TreasuryManager,
+}
+
+/// Map for storing the roles granted to each account (address, roles)
+pub const ROLES: Map<&Addr, Vec<Role>> = Map::new("roles");
+
+/// Map for storing the funds the contract owes to NFTs and savings plans (denom, amount)
+pub const TOTAL_DEPOSITS: Map<&str, Uint128> = Map::new("total_deposits");
+
+/// A crowdfunding goal, mint proceeds are held until `min_tokens` NFTs are sold
+#[cw_serde]
+pub struct MinRaise {
+    /// Number of NFTs that must be sold for the raise to succeed
+    pub min_tokens: u64,
+    /// If the raise hasn't succeeded by this time, NFTs can be burned for a refund
+    pub deadline: Timestamp,
+}
+
+/// The minimum raise, if any
+pub const MIN_RAISE: Item<MinRaise> = Item::new("min_raise");
+
+/// Number of NFTs sold towards the minimum raise, refunded NFTs don't count
+pub const RAISE_MINTED: Item<u64> = Item::new("raise_minted");
+
+/// Mint proceeds held until the minimum raise succeeds
+pub const ESCROWED_PROCEEDS: Item<Uint128> = Item::new("escrowed_proceeds");
+
+/// Map for storing what was paid to mint an NFT while its proceeds are escrowed (token_id, amount)
+pub const MINT_PAYMENTS: Map<&str, Uint128> = Map::new("mint_payments");
+
+/// A mint price that decays from `start_price` to `floor_price` over time
+#[cw_serde]
+pub struct DutchAuction {
+    pub start_price: Uint128,
+    pub floor_price: Uint128,
+    /// Amount the price drops by every `decay_interval`
+    pub decay: Uint128,
+    pub decay_interval: Duration,
+    /// If true, buyers can claim back what they paid above the clearing price
+    pub rebate: bool,
+    /// Block height the auction started at
+    pub start_height: u64,
+    /// Time the auction started at
+    pub start_time: Timestamp,
+}
+
+/// The Dutch auction, if any
+pub const AUCTION: Item<DutchAuction> = Item::new("auction");
+
+/// Sales made in a Dutch auction with rebates
+#[cw_serde]
+#[derive(Default)]
+pub struct AuctionProgress {
+    /// Number of NFTs sold in the auction
+    pub sales: u64,
+    /// The price of the sale that sold out the collection, if it sold out above the floor
+    pub clearing_price: Option<Uint128>,
+    /// True once the sale funds recipient has been paid its share above the floor
+    pub released: bool,
+}
+
+/// The progress of the Dutch auction
+pub const AUCTION_PROGRESS: Item<AuctionProgress> = Item::new("auction_progress");
+
+/// What a buyer paid above the floor price in a Dutch auction with rebates
+#[cw_serde]
+pub struct AuctionPurchase {
+    /// Number of NFTs bought
+    pub count: u64,
+    /// Total paid above the floor price, held until the auction settles
+    pub held: Uint128,
+}
+
+/// Map for storing Dutch auction purchases awaiting a rebate (buyer, purchase)
+pub const AUCTION_PURCHASES: Map<&Addr, AuctionPurchase> = Map::new("auction_purchases");
+
+/// A mint price that rises with the number of NFTs minted
+#[cw_serde]
+pub enum BondingCurve {
+    /// The nth NFT costs `base_price + increment * (n - 1)`
+    Linear {
+        base_price: Uint128,
+        increment: Uint128,
+    },
+    /// The nth NFT costs `base_price * (1 + growth_bps / 10000) ^ (n - 1)`
+    Exponential {
+        base_price: Uint128,
+        growth_bps: u64,
+    },
+}
+
+/// The bonding curve, if any
+pub const BONDING_CURVE: Item<BondingCurve> = Item::new("bonding_curve");
+
+/// Another way to pay for a mint, at its own price
+#[cw_serde]
+pub enum PaymentOption {
+    Native { denom: String, amount: Uint128 },
+    Cw20 { address: Addr, amount: Uint128 },
+}
+
+/// Payment options accepted besides the mint price
+pub const PAYMENT_OPTIONS: Item<Vec<PaymentOption>> = Item::new("payment_options");
+
+/// Share of the mint proceeds deposited into the new NFT, in basis points
+pub const MINT_SEED: Item<u64> = Item::new("mint_seed");
+
+/// Max NFTs a wallet can buy, if any
+pub const MAX_MINTS_PER_WALLET: Item<u64> = Item::new("max_mints_per_wallet");
+
+/// NFTs bought by each wallet
+pub const WALLET_MINTS: Map<&Addr, u64> = Map::new("wallet_mints");
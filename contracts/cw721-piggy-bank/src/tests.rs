@@ -1,15 +1,110 @@
 use crate::{
     contract::{execute, instantiate, query, CONTRACT_NAME},
-    msg::{ExecuteExt, ExecuteMsg, InstantiateMsg, MetadataExt, QueryMsg},
+    msg::{
+        BeneficiaryMsg, BeneficiaryResponse, BreakRequestsResponse, ConfigResponse, Contribution,
+        ContributionsResponse, DenomSolvency, DepositLimitsResponse, DutchAuctionMsg, ExecuteExt,
+        ExecuteMsg, FeeMsg, InstantiateMsg, LockResponse, MetadataExt, PaymentOptionMsg,
+        PaymentOptionsResponse, PlanResponse, PlansResponse, QueryExt, QueryMsg, RaiseResponse,
+        RaiseStatus, ReceiveMsg, RecoveryMsg, RecoveryResponse, RolesResponse,
+        SimulateBurnResponse, SimulateDepositResponse, SolvencyResponse,
+    },
+    state::{BondingCurve, MinRaise, Role},
     ContractError,
 };
 
 use cosmwasm_std::{
-    coin, coins, from_binary,
-    testing::{mock_dependencies, mock_env, mock_info},
-    BankMsg, CosmosMsg, StdError,
+    attr, coin, coins, from_binary, from_slice,
+    testing::{
+        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+        MOCK_CONTRACT_ADDR,
+    },
+    to_binary, Addr, BankMsg, Coin, CosmosMsg, DepsMut, Env, OwnedDeps, StdError, Uint128, WasmMsg,
 };
-use cw721::{AllNftInfoResponse, TokensResponse};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw721::{AllNftInfoResponse, OwnerOfResponse, TokensResponse};
+use cw721_base::{Action, Ownership};
+use cw_utils::{Duration, Expiration};
+
+const BASE_URL: &str =
+    "https://bafybeie2grcflzjvds7i33bxjjgktjdfcp2h2v27gdkbyuiaelvbgtdewy.ipfs.nftstorage.link";
+
+/// Instantiate message with every optional feature turned off
+fn default_instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        name: "1337".into(),
+        symbol: "1337".into(),
+        minter: "bob".into(),
+        deposit_denom: "ujuno".into(),
+        mint_price: coin(1000000, "ujuno"),
+        base_url: BASE_URL.to_string(),
+        max_nft_supply: None,
+        max_splits: None,
+        sale_funds_recipient: "larry".into(),
+        min_deposit: None,
+        max_token_balance: None,
+        fee: None,
+        early_break_penalty_bps: None,
+        require_expected_balance: false,
+        transfer_cooldown: None,
+        soulbound: false,
+        allow_admin_recovery: false,
+        guardian_timeout: None,
+        min_raise: None,
+        dutch_auction: None,
+        bonding_curve: None,
+        payment_options: vec![],
+        mint_seed_bps: None,
+        max_mints_per_wallet: None,
+    }
+}
+
+/// Instantiates the contract and mints token "1" to `owner`
+fn setup_with_token(
+    msg: InstantiateMsg,
+    owner: &str,
+) -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+    let minter = msg.minter.clone();
+    instantiate(deps.as_mut(), mock_env(), mock_info(&minter, &[]), msg).unwrap();
+    mint_to(&mut deps, owner, "1");
+    deps
+}
+
+/// Mints the next token, paying the mint price as `owner`
+fn mint_to(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, owner: &str, token_id: &str) {
+    let config: ConfigResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Config {},
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(owner, &[config.mint_price]),
+        ExecuteMsg::Mint {
+            token_id: token_id.into(),
+            owner: owner.into(),
+            token_uri: None,
+            extension: MetadataExt {},
+            guardian: None,
+        },
+    )
+    .unwrap();
+}
+
+fn deposit(token_id: &str) -> ExecuteMsg {
+    ExecuteMsg::Extension {
+        msg: ExecuteExt::Deposit {
+            token_id: token_id.into(),
+        },
+    }
+}
 
 /// Make sure cw2 version info is properly initialized during instantiation,
 /// and NOT overwritten by the base contract.
@@ -30,6 +125,7 @@ fn proper_cw2_initialization() {
             base_url: "https://bafybeie2grcflzjvds7i33bxjjgktjdfcp2h2v27gdkbyuiaelvbgtdewy.ipfs.nftstorage.link".to_string(),
             max_nft_supply: Some(2),
             sale_funds_recipient: "larry".into(),
+            ..default_instantiate_msg()
         },
     )
     .unwrap();
@@ -39,6 +135,27 @@ fn proper_cw2_initialization() {
     assert_ne!(version.contract, cw721_base::CONTRACT_NAME);
 }
 
+/// Instantiate messages written before the newer options were added still parse
+#[test]
+fn instantiate_msg_defaults() {
+    let msg: InstantiateMsg = from_slice(
+        br#"{
+            "name": "1337",
+            "symbol": "1337",
+            "minter": "bob",
+            "deposit_denom": "ujuno",
+            "mint_price": { "denom": "ujuno", "amount": "1000000" },
+            "base_url": "https://example.com",
+            "sale_funds_recipient": "larry"
+        }"#,
+    )
+    .unwrap();
+    assert!(!msg.require_expected_balance);
+    assert!(!msg.soulbound);
+    assert!(!msg.allow_admin_recovery);
+    assert!(msg.payment_options.is_empty());
+}
+
 #[test]
 fn happy_path() {
     let mut deps = mock_dependencies();
@@ -57,6 +174,7 @@ fn happy_path() {
             base_url: "https://bafybeie2grcflzjvds7i33bxjjgktjdfcp2h2v27gdkbyuiaelvbgtdewy.ipfs.nftstorage.link".to_string(),
             max_nft_supply: Some(2),
             sale_funds_recipient: "larry".into(),
+            ..default_instantiate_msg()
         },
     )
     .unwrap();
@@ -71,6 +189,7 @@ fn happy_path() {
             owner: BOB.into(),
             token_uri: Some("https://bafybeie2grcflzjvds7i33bxjjgktjdfcp2h2v27gdkbyuiaelvbgtdewy.ipfs.nftstorage.link/1/seedling.json".to_string()),
             extension: MetadataExt {},
+            guardian: None,
         },
     )
     .unwrap_err();
@@ -87,6 +206,7 @@ fn happy_path() {
             owner: BOB.into(),
             token_uri: Some("https://bafybeie2grcflzjvds7i33bxjjgktjdfcp2h2v27gdkbyuiaelvbgtdewy.ipfs.nftstorage.link/1/seedling.json".to_string()),
             extension: MetadataExt {},
+            guardian: None,
         },
     )
     .unwrap();
@@ -247,3 +367,3129 @@ fn happy_path() {
         })
     );
 }
+
+#[test]
+fn deposit_limits() {
+    // The minimum can't be greater than the maximum
+    let mut deps = mock_dependencies();
+    let err = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        InstantiateMsg {
+            min_deposit: Some(Uint128::new(100)),
+            max_token_balance: Some(Uint128::new(10)),
+            ..default_instantiate_msg()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidDepositLimits {});
+
+    let mut deps = setup_with_token(
+        InstantiateMsg {
+            min_deposit: Some(Uint128::new(100)),
+            max_token_balance: Some(Uint128::new(1000)),
+            ..default_instantiate_msg()
+        },
+        "alice",
+    );
+
+    // Limits show up in the config query
+    let config: ConfigResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Config {},
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(config.min_deposit, Some(Uint128::new(100)));
+    assert_eq!(config.max_token_balance, Some(Uint128::new(1000)));
+
+    // Dust deposits are rejected
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("rando", &coins(99, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DepositTooSmall {
+            min: Uint128::new(100)
+        }
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("rando", &coins(100, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap();
+
+    // Only the owner can raise the minimum for their token
+    let set_min_deposit = ExecuteMsg::Extension {
+        msg: ExecuteExt::SetMinDeposit {
+            token_id: "1".into(),
+            amount: Some(Uint128::new(500)),
+        },
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("rando", &[]),
+        set_min_deposit.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        set_min_deposit,
+    )
+    .unwrap();
+
+    let limits: DepositLimitsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::DepositLimits {
+                    token_id: "1".into(),
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(limits.min_deposit, Some(Uint128::new(500)));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("rando", &coins(200, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DepositTooSmall {
+            min: Uint128::new(500)
+        }
+    );
+
+    // Deposits can't push the balance over the cap
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("rando", &coins(901, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MaxBalanceExceeded {
+            max: Uint128::new(1000)
+        }
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("rando", &coins(900, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap();
+}
+
+#[test]
+fn savings_plans() {
+    let mut deps = setup_with_token(default_instantiate_msg(), "alice");
+    let balance = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>| -> Uint128 {
+        let balance: Coin = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Extension {
+                    msg: QueryExt::Balance {
+                        token_id: "1".into(),
+                    },
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        balance.amount
+    };
+    let create_plan = ExecuteMsg::Extension {
+        msg: ExecuteExt::CreatePlan {
+            token_id: "1".into(),
+            amount: Uint128::new(100),
+            interval: Duration::Time(60),
+            tip: Uint128::new(10),
+        },
+    };
+
+    // Only the owner can create a plan, and it must fund one installment
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("rando", &coins(250, "ujuno")),
+        create_plan.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(100, "ujuno")),
+        create_plan.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongAmount {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(250, "ujuno")),
+        create_plan,
+    )
+    .unwrap();
+
+    // Nothing is due yet
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::Crank { limit: None },
+        },
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(balance(&deps), Uint128::zero());
+
+    // The keeper is tipped for executing a due plan
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(60);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::Crank { limit: None },
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "keeper".to_string(),
+            amount: coins(10, "ujuno"),
+        })
+    );
+    assert_eq!(balance(&deps), Uint128::new(100));
+
+    // The last installment closes the plan and refunds the leftover funds
+    env.block.time = env.block.time.plus_seconds(60);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("keeper", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::Crank { limit: None },
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".to_string(),
+            amount: coins(30, "ujuno"),
+        })
+    );
+    assert_eq!(balance(&deps), Uint128::new(200));
+    let plans: PlansResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Plans {
+                    start_after: None,
+                    limit: None,
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(plans.plans.is_empty());
+
+    // Cancelling a plan refunds it in full
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(500, "ujuno")),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::CreatePlan {
+                token_id: "1".into(),
+                amount: Uint128::new(100),
+                interval: Duration::Height(10),
+                tip: Uint128::zero(),
+            },
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::CancelPlan { plan_id: 2 },
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".to_string(),
+            amount: coins(500, "ujuno"),
+        })
+    );
+
+    // Plans far in the future don't hold up due plans stored behind them
+    for _ in 0..3 {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &coins(100, "ujuno")),
+            ExecuteMsg::Extension {
+                msg: ExecuteExt::CreatePlan {
+                    token_id: "1".into(),
+                    amount: Uint128::new(100),
+                    interval: Duration::Time(365 * 24 * 60 * 60),
+                    tip: Uint128::zero(),
+                },
+            },
+        )
+        .unwrap();
+    }
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(110, "ujuno")),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::CreatePlan {
+                token_id: "1".into(),
+                amount: Uint128::new(100),
+                interval: Duration::Height(1),
+                tip: Uint128::new(10),
+            },
+        },
+    )
+    .unwrap();
+    let mut env = mock_env();
+    env.block.height += 1;
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("keeper", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::Crank { limit: Some(1) },
+        },
+    )
+    .unwrap();
+    assert_eq!(res.attributes[1], attr("executed", "1"));
+    assert_eq!(balance(&deps), Uint128::new(300));
+
+    // An installment and tip that overflow are rejected
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(100, "ujuno")),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::CreatePlan {
+                token_id: "1".into(),
+                amount: Uint128::MAX,
+                interval: Duration::Height(1),
+                tip: Uint128::new(1),
+            },
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Overflow(_)));
+}
+
+#[test]
+fn relayed_deposits() {
+    let mut deps = setup_with_token(default_instantiate_msg(), "alice");
+    let deposit_for = ExecuteMsg::Extension {
+        msg: ExecuteExt::DepositFor {
+            token_id: "1".into(),
+            contributor: "grandma".into(),
+            beneficiary_note: Some("happy birthday".into()),
+        },
+    };
+
+    // Untrusted contracts can't attribute deposits to someone else
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("relayer", &coins(100, "ujuno")),
+        deposit_for.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Only the minter can manage the relayer registry
+    let update_relayers = ExecuteMsg::Extension {
+        msg: ExecuteExt::UpdateRelayers {
+            add: vec!["relayer".into()],
+            remove: vec![],
+        },
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("rando", &[]),
+        update_relayers.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        update_relayers,
+    )
+    .unwrap();
+
+    // The deposit is attributed to the end user, not the relayer
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("relayer", &coins(100, "ujuno")),
+        deposit_for,
+    )
+    .unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "contributor" && attr.value == "grandma"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(50, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap();
+
+    let contributions: ContributionsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Contributions {
+                    token_id: "1".into(),
+                    start_after: None,
+                    limit: None,
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        contributions.contributions,
+        vec![
+            Contribution {
+                contributor: Addr::unchecked("alice"),
+                amount: Uint128::new(50),
+            },
+            Contribution {
+                contributor: Addr::unchecked("grandma"),
+                amount: Uint128::new(100),
+            },
+        ]
+    );
+}
+
+#[test]
+fn break_open_payout_recipient() {
+    let mut deps = setup_with_token(default_instantiate_msg(), "alice");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(100, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::ApproveAll {
+            operator: "market".into(),
+            expires: None,
+        },
+    )
+    .unwrap();
+    let break_open = |recipient: Option<&str>| ExecuteMsg::Extension {
+        msg: ExecuteExt::BreakOpen {
+            token_id: "1".into(),
+            recipient: recipient.map(Into::into),
+        },
+    };
+
+    // Operators can't send the payout anywhere but the owner
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("market", &[]),
+        break_open(Some("market")),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Unless the owner opts in
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::AllowOperatorPayout {
+                token_id: "1".into(),
+                allowed: true,
+            },
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("market", &[]),
+        break_open(Some("vault")),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "vault".to_string(),
+            amount: coins(100, "ujuno"),
+        })
+    );
+
+    // A plain burn by an operator pays the owner
+    let mut deps = setup_with_token(default_instantiate_msg(), "alice");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(100, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::ApproveAll {
+            operator: "market".into(),
+            expires: None,
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("market", &[]),
+        ExecuteMsg::Burn {
+            token_id: "1".into(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".to_string(),
+            amount: coins(100, "ujuno"),
+        })
+    );
+}
+
+#[test]
+fn withdrawal_fee_and_locks() {
+    let mut deps = setup_with_token(
+        InstantiateMsg {
+            fee: Some(FeeMsg {
+                bps: 250,
+                treasury: "treasury".into(),
+                waive_after_maturity: true,
+                min_lock_duration: 86400,
+            }),
+            ..default_instantiate_msg()
+        },
+        "alice",
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(1000, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap();
+
+    // Only a treasury manager can update the fee, and it can't exceed 10%
+    let update_fee = |bps| ExecuteMsg::Extension {
+        msg: ExecuteExt::UpdateFee {
+            fee: Some(FeeMsg {
+                bps,
+                treasury: "treasury".into(),
+                waive_after_maturity: true,
+                min_lock_duration: 86400,
+            }),
+        },
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        update_fee(500),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        update_fee(1001),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::FeeTooHigh { max: 1000 });
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        update_fee(500),
+    )
+    .unwrap();
+    let config: ConfigResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Config {},
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(config.fee.unwrap().bps, 500);
+
+    // Lock the piggy bank for a day, it can't be broken open until then
+    let until = mock_env().block.time.plus_seconds(86400);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::Lock {
+                token_id: "1".into(),
+                until,
+            },
+        },
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::Lock {
+                token_id: "1".into(),
+                until: until.minus_seconds(1),
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidLock {});
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Burn {
+            token_id: "1".into(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Locked { until });
+
+    // Held until maturity, so no fee is taken
+    let mut env = mock_env();
+    env.block.time = until;
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("alice", &[]),
+        ExecuteMsg::Burn {
+            token_id: "1".into(),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".to_string(),
+            amount: coins(1000, "ujuno"),
+        })
+    );
+
+    // Piggy banks unlocked or locked for less than the minimum pay the fee to the treasury
+    let mut deps = setup_with_token(
+        InstantiateMsg {
+            fee: Some(FeeMsg {
+                bps: 250,
+                treasury: "treasury".into(),
+                waive_after_maturity: true,
+                min_lock_duration: 86400,
+            }),
+            ..default_instantiate_msg()
+        },
+        "alice",
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(1000, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::Lock {
+                token_id: "1".into(),
+                until: mock_env().block.time.plus_seconds(1),
+            },
+        },
+    )
+    .unwrap();
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(1);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("alice", &[]),
+        ExecuteMsg::Burn {
+            token_id: "1".into(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".to_string(),
+            amount: coins(975, "ujuno"),
+        })
+    );
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "treasury".to_string(),
+            amount: coins(25, "ujuno"),
+        })
+    );
+
+    // Funds deposited once the lock has matured weren't locked, so they pay the fee
+    let mut deps = setup_with_token(
+        InstantiateMsg {
+            fee: Some(FeeMsg {
+                bps: 250,
+                treasury: "treasury".into(),
+                waive_after_maturity: true,
+                min_lock_duration: 86400,
+            }),
+            ..default_instantiate_msg()
+        },
+        "alice",
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::Lock {
+                token_id: "1".into(),
+                until,
+            },
+        },
+    )
+    .unwrap();
+    let mut env = mock_env();
+    env.block.time = until;
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("alice", &coins(1000, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("alice", &[]),
+        ExecuteMsg::Burn {
+            token_id: "1".into(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "treasury".to_string(),
+            amount: coins(25, "ujuno"),
+        })
+    );
+}
+
+#[test]
+fn early_break_penalty() {
+    let mut deps = setup_with_token(
+        InstantiateMsg {
+            early_break_penalty_bps: Some(1000),
+            ..default_instantiate_msg()
+        },
+        "alice",
+    );
+    mint_to(&mut deps, "carol", "2");
+    mint_to(&mut deps, "dave", "3");
+    for token_id in ["1", "2", "3"] {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("rando", &coins(1000, "ujuno")),
+            deposit(token_id),
+        )
+        .unwrap();
+    }
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::Lock {
+                token_id: "1".into(),
+                until: mock_env().block.time.plus_seconds(86400),
+            },
+        },
+    )
+    .unwrap();
+
+    // Preview what breaking early pays out
+    let simulation: SimulateBurnResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::SimulateBurn {
+                    token_id: "1".into(),
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        simulation,
+        SimulateBurnResponse {
+            payout: Uint128::new(900),
+            fee: Uint128::zero(),
+            penalty: Uint128::new(100),
+            mint_refund: None,
+        }
+    );
+
+    // Breaking early forfeits 10%, split between the other two piggy banks
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Burn {
+            token_id: "1".into(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".to_string(),
+            amount: coins(900, "ujuno"),
+        })
+    );
+    let balance: Coin = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Balance {
+                    token_id: "2".into(),
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(balance.amount, Uint128::new(1050));
+
+    // The share is paid out when the other piggy banks are broken
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("dave", &[]),
+        ExecuteMsg::Burn {
+            token_id: "3".into(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "dave".to_string(),
+            amount: coins(1050, "ujuno"),
+        })
+    );
+}
+
+#[test]
+fn simulate_deposit() {
+    let mut deps = setup_with_token(
+        InstantiateMsg {
+            max_token_balance: Some(Uint128::new(50000000)),
+            ..default_instantiate_msg()
+        },
+        "alice",
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(1000000, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap();
+
+    let simulate_deposit = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, amount| {
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::SimulateDeposit {
+                    token_id: "1".into(),
+                    amount: Uint128::new(amount),
+                },
+            },
+        )
+        .map(|res| from_binary::<SimulateDepositResponse>(&res).unwrap())
+    };
+
+    // Depositing moves the piggy bank into the tree stage
+    let simulation = simulate_deposit(&deps, 10000000).unwrap();
+    assert_eq!(simulation.balance, coin(11000000, "ujuno"));
+    assert_eq!(simulation.stage, "tree");
+    assert_eq!(simulation.token_uri, format!("{}/1/tree.json", BASE_URL));
+
+    // Rejected deposits error, the same as executing them
+    simulate_deposit(&deps, 50000000).unwrap_err();
+
+    // The simulation matches what the deposit does
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(10000000, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap();
+    let token: AllNftInfoResponse<MetadataExt> = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AllNftInfo {
+                token_id: "1".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(token.info.token_uri, Some(simulation.token_uri));
+}
+
+#[test]
+fn merge() {
+    let mut deps = setup_with_token(default_instantiate_msg(), "alice");
+    mint_to(&mut deps, "alice", "2");
+    mint_to(&mut deps, "carol", "3");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("grandma", &coins(1500000, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(500000, "ujuno")),
+        deposit("2"),
+    )
+    .unwrap();
+    let lock = |token_id: &str, seconds| ExecuteMsg::Extension {
+        msg: ExecuteExt::Lock {
+            token_id: token_id.into(),
+            until: mock_env().block.time.plus_seconds(seconds),
+        },
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        lock("1", 1000),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        lock("2", 100),
+    )
+    .unwrap();
+    let merge = |source: &str, target: &str| ExecuteMsg::Extension {
+        msg: ExecuteExt::Merge {
+            source: source.into(),
+            target: target.into(),
+        },
+    };
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(100, "ujuno")),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::CreatePlan {
+                token_id: "1".into(),
+                amount: Uint128::new(100),
+                interval: Duration::Height(10),
+                tip: Uint128::zero(),
+            },
+        },
+    )
+    .unwrap();
+
+    // Can only merge NFTs you own
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        merge("1", "3"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Merging doesn't pay anything out
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        merge("1", "2"),
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+
+    // The target holds everything and has grown into a sapling
+    let token: AllNftInfoResponse<MetadataExt> = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AllNftInfo {
+                token_id: "2".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        token.info.token_uri,
+        Some(format!("{}/2/sapling.json", BASE_URL))
+    );
+    let contributions: ContributionsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Contributions {
+                    token_id: "2".into(),
+                    start_after: None,
+                    limit: None,
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(contributions.contributions.len(), 2);
+    let lock: LockResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Lock {
+                    token_id: "2".into(),
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(lock.until, Some(mock_env().block.time.plus_seconds(1000)));
+
+    // Savings plans now save into the target
+    let plan: PlanResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Plan { plan_id: 1 },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(plan.plan.token_id, "2");
+
+    // The source is gone
+    query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::OwnerOf {
+            token_id: "1".into(),
+            include_expired: None,
+        },
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn split() {
+    let mut deps = setup_with_token(
+        InstantiateMsg {
+            max_nft_supply: Some(2),
+            max_splits: Some(1),
+            min_deposit: Some(Uint128::new(100)),
+            ..default_instantiate_msg()
+        },
+        "alice",
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(3000000, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap();
+    let split = |amount| ExecuteMsg::Extension {
+        msg: ExecuteExt::Split {
+            token_id: "1".into(),
+            amount: Uint128::new(amount),
+            recipient: "kid".into(),
+        },
+    };
+
+    // Only the owner can split, and only what's in the piggy bank
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("kid", &[]),
+        split(1000000),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        split(3000001),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidSplit {});
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        split(99),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DepositTooSmall {
+            min: Uint128::new(100)
+        }
+    );
+
+    // Carve out a gift piggy bank
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        split(2500000),
+    )
+    .unwrap();
+    let balance = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, token_id: &str| {
+        from_binary::<Coin>(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Extension {
+                    msg: QueryExt::Balance {
+                        token_id: token_id.into(),
+                    },
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .amount
+    };
+    assert_eq!(balance(&deps, "1"), Uint128::new(500000));
+    assert_eq!(balance(&deps, "2"), Uint128::new(2500000));
+
+    // The gift belongs to the kid, and the original shrinks back to a seedling
+    let token: AllNftInfoResponse<MetadataExt> = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AllNftInfo {
+                token_id: "2".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(token.access.owner, "kid");
+    assert_eq!(
+        token.info.token_uri,
+        Some(format!("{}/2/sapling.json", BASE_URL))
+    );
+    let token: AllNftInfoResponse<MetadataExt> = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AllNftInfo {
+                token_id: "1".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        token.info.token_uri,
+        Some(format!("{}/1/seedling.json", BASE_URL))
+    );
+
+    // Splits have their own allowance and don't use up the sale supply
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        split(100),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SplitLimitReached { max: 1 });
+    mint_to(&mut deps, "carol", "3");
+}
+
+#[test]
+fn transfer_protections() {
+    let mut deps = setup_with_token(
+        InstantiateMsg {
+            require_expected_balance: true,
+            transfer_cooldown: Some(3600),
+            ..default_instantiate_msg()
+        },
+        "alice",
+    );
+
+    // Empty piggy banks can be transferred as normal
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::TransferNft {
+            recipient: "carol".into(),
+            token_id: "1".into(),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("carol", &coins(1000, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap();
+
+    // Funded ones need the buyer's expected balance
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("carol", &[]),
+        ExecuteMsg::TransferNft {
+            recipient: "alice".into(),
+            token_id: "1".into(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ExpectedBalanceRequired {});
+    let transfer = |expected_balance| ExecuteMsg::Extension {
+        msg: ExecuteExt::TransferWithBalance {
+            recipient: "alice".into(),
+            token_id: "1".into(),
+            expected_balance: Uint128::new(expected_balance),
+        },
+    };
+
+    // A withdrawal before the sale makes the transfer fail
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("carol", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::Split {
+                token_id: "1".into(),
+                amount: Uint128::new(400),
+                recipient: "carol".into(),
+            },
+        },
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("carol", &[]),
+        transfer(1000),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BalanceMismatch {
+            expected: Uint128::new(1000),
+            actual: Uint128::new(600),
+        }
+    );
+
+    // And the NFT can't change hands until the cooldown has passed
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("carol", &[]),
+        transfer(600),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TransferCooldown {
+            until: mock_env().block.time.plus_seconds(3600)
+        }
+    );
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(3600);
+    execute(deps.as_mut(), env, mock_info("carol", &[]), transfer(600)).unwrap();
+}
+
+#[test]
+fn soulbound() {
+    let mut deps = setup_with_token(
+        InstantiateMsg {
+            soulbound: true,
+            allow_admin_recovery: true,
+            ..default_instantiate_msg()
+        },
+        "kid",
+    );
+
+    // Can't be sold or approved for sale
+    for msg in [
+        ExecuteMsg::TransferNft {
+            recipient: "buyer".into(),
+            token_id: "1".into(),
+        },
+        ExecuteMsg::Approve {
+            spender: "market".into(),
+            token_id: "1".into(),
+            expires: None,
+        },
+        ExecuteMsg::ApproveAll {
+            operator: "market".into(),
+            expires: None,
+        },
+    ] {
+        let err = execute(deps.as_mut(), mock_env(), mock_info("kid", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Soulbound {});
+    }
+
+    // Still a piggy bank
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("grandma", &coins(1000, "ujuno")),
+        deposit("1"),
+    )
+    .unwrap();
+
+    // The value can't be split off to someone else either
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("kid", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::Split {
+                token_id: "1".into(),
+                amount: Uint128::new(100),
+                recipient: "buyer".into(),
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Soulbound {});
+
+    // The minter can recover it to a new wallet
+    let recovery_transfer = ExecuteMsg::Extension {
+        msg: ExecuteExt::RecoveryTransfer {
+            token_id: "1".into(),
+            recipient: "kid2".into(),
+        },
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("kid", &[]),
+        recovery_transfer.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        recovery_transfer,
+    )
+    .unwrap();
+
+    // And the new owner can break it open
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("kid2", &[]),
+        ExecuteMsg::Burn {
+            token_id: "1".into(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "kid2".to_string(),
+            amount: coins(1000, "ujuno"),
+        })
+    );
+
+    // Admin recovery can't be allowed on transferable NFTs
+    let err = instantiate(
+        mock_dependencies().as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        InstantiateMsg {
+            allow_admin_recovery: true,
+            ..default_instantiate_msg()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidAdminRecovery {});
+}
+
+#[test]
+fn guardian_approval() {
+    let mut deps = setup_with_token(
+        InstantiateMsg {
+            guardian_timeout: Some(604800),
+            ..default_instantiate_msg()
+        },
+        "parent",
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("parent", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::SetGuardian {
+                token_id: "1".into(),
+                guardian: Some("parent".into()),
+            },
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("parent", &[]),
+        ExecuteMsg::TransferNft {
+            recipient: "kid".into(),
+            token_id: "1".into(),
+        },
+    )
+    .unwrap();
+
+    // The kid can't remove the guardian
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("kid", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::SetGuardian {
+                token_id: "1".into(),
+                guardian: None,
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Or break it open without asking
+    let burn = ExecuteMsg::Burn {
+        token_id: "1".into(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("kid", &[]),
+        burn.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::GuardianApprovalRequired {});
+    query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Extension {
+            msg: QueryExt::SimulateBurn {
+                token_id: "1".into(),
+            },
+        },
+    )
+    .unwrap_err();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("kid", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::RequestBreak {
+                token_id: "1".into(),
+            },
+        },
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("kid", &[]),
+        burn.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::GuardianApprovalRequired {});
+
+    // The pending request shows up for the guardian
+    let requests: BreakRequestsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::BreakRequests {
+                    start_after: None,
+                    limit: None,
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(requests.requests.len(), 1);
+
+    // Once approved, it can be broken open
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("kid", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::ApproveBreak {
+                token_id: "1".into(),
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("parent", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::ApproveBreak {
+                token_id: "1".into(),
+            },
+        },
+    )
+    .unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("kid", &[]), burn).unwrap();
+
+    // Requests that go unanswered time out
+    mint_to(&mut deps, "kid", "2");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("kid", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::SetGuardian {
+                token_id: "2".into(),
+                guardian: Some("parent".into()),
+            },
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("kid", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::RequestBreak {
+                token_id: "2".into(),
+            },
+        },
+    )
+    .unwrap();
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(604800);
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("kid", &[]),
+        ExecuteMsg::Burn {
+            token_id: "2".into(),
+        },
+    )
+    .unwrap();
+
+    // A guardian can be set when buying an NFT for someone else
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("parent", &coins(1000000, "ujuno")),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::PurchaseBatch {
+                count: 1,
+                recipient: Some("kid".into()),
+                guardian: Some("parent".into()),
+            },
+        },
+    )
+    .unwrap();
+    let token_id = res
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "token_id")
+        .unwrap()
+        .value
+        .clone();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("kid", &[]),
+        ExecuteMsg::Burn { token_id },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::GuardianApprovalRequired {});
+}
+
+#[test]
+fn social_recovery() {
+    let mut deps = setup_with_token(default_instantiate_msg(), "alice");
+    let recover = |signer: &str, new_owner: &str, env: Env, deps: DepsMut| {
+        execute(
+            deps,
+            env,
+            mock_info(signer, &[]),
+            ExecuteMsg::Extension {
+                msg: ExecuteExt::Recover {
+                    token_id: "1".into(),
+                    new_owner: new_owner.into(),
+                },
+            },
+        )
+    };
+
+    // Threshold can't exceed the number of distinct addresses
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::SetRecovery {
+                token_id: "1".into(),
+                recovery: Some(RecoveryMsg {
+                    addresses: vec!["friend1".into(), "friend1".into()],
+                    threshold: 2,
+                    delay: 86400,
+                }),
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidRecovery {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::SetRecovery {
+                token_id: "1".into(),
+                recovery: Some(RecoveryMsg {
+                    addresses: vec!["friend1".into(), "friend2".into(), "friend3".into()],
+                    threshold: 2,
+                    delay: 86400,
+                }),
+            },
+        },
+    )
+    .unwrap();
+
+    // Only recovery addresses can sign
+    let err = recover("mallory", "mallory", mock_env(), deps.as_mut()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Signatures for different owners don't add up
+    recover("friend1", "alice2", mock_env(), deps.as_mut()).unwrap();
+    recover("friend2", "mallory", mock_env(), deps.as_mut()).unwrap();
+    let res: RecoveryResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Recovery {
+                    token_id: "1".into(),
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.votes.len(), 2);
+    assert_eq!(res.pending, None);
+
+    // The owner can cancel once the threshold is reached
+    recover("friend3", "mallory", mock_env(), deps.as_mut()).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::CancelRecovery {
+                token_id: "1".into(),
+            },
+        },
+    )
+    .unwrap();
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(86400);
+    recover("friend3", "mallory", env, deps.as_mut()).unwrap();
+
+    // Otherwise the NFT moves once the delay has passed
+    recover("friend1", "alice2", mock_env(), deps.as_mut()).unwrap();
+    recover("friend2", "alice2", mock_env(), deps.as_mut()).unwrap();
+    let err = recover("friend2", "alice2", mock_env(), deps.as_mut()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::RecoveryDelay {
+            until: mock_env().block.time.plus_seconds(86400)
+        }
+    );
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(86400);
+    recover("friend1", "alice2", env, deps.as_mut()).unwrap();
+
+    let owner: OwnerOfResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::OwnerOf {
+                token_id: "1".into(),
+                include_expired: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(owner.owner, "alice2");
+
+    // The old setup doesn't carry over to the new owner
+    let err = recover("friend1", "mallory", mock_env(), deps.as_mut()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // A pending recovery is dropped once signers change their minds
+    let mut deps = setup_with_token(default_instantiate_msg(), "alice");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::SetRecovery {
+                token_id: "1".into(),
+                recovery: Some(RecoveryMsg {
+                    addresses: vec!["friend1".into(), "friend2".into(), "friend3".into()],
+                    threshold: 2,
+                    delay: 86400,
+                }),
+            },
+        },
+    )
+    .unwrap();
+    recover("friend1", "mallory", mock_env(), deps.as_mut()).unwrap();
+    recover("friend2", "mallory", mock_env(), deps.as_mut()).unwrap();
+    recover("friend2", "alice2", mock_env(), deps.as_mut()).unwrap();
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(86400);
+    let res = recover("friend1", "mallory", env, deps.as_mut()).unwrap();
+    assert_eq!(res.attributes[2], attr("action", "sign_recovery"));
+    let owner: OwnerOfResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::OwnerOf {
+                token_id: "1".into(),
+                include_expired: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(owner.owner, "alice");
+}
+
+#[test]
+fn inheritance() {
+    let mut deps = setup_with_token(default_instantiate_msg(), "alice");
+    let day = 86400;
+    let at = |seconds: u64| {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(seconds);
+        env
+    };
+    let claim = ExecuteMsg::Extension {
+        msg: ExecuteExt::ClaimInheritance {
+            token_id: "1".into(),
+        },
+    };
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::SetBeneficiary {
+                token_id: "1".into(),
+                beneficiary: Some(BeneficiaryMsg {
+                    address: "heir".into(),
+                    inactivity_period: 30 * day,
+                }),
+            },
+        },
+    )
+    .unwrap();
+
+    // Deposits by others don't count as owner activity
+    execute(
+        deps.as_mut(),
+        at(10 * day),
+        mock_info("grandma", &coins(500, "ujuno")),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::Deposit {
+                token_id: "1".into(),
+            },
+        },
+    )
+    .unwrap();
+
+    // Pinging resets the clock
+    execute(
+        deps.as_mut(),
+        at(20 * day),
+        mock_info("alice", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::Ping {
+                token_id: "1".into(),
+            },
+        },
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        at(30 * day),
+        mock_info("heir", &[]),
+        claim.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InheritanceNotClaimable {
+            until: at(50 * day).block.time
+        }
+    );
+
+    let res: BeneficiaryResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Beneficiary {
+                    token_id: "1".into(),
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.claimable_at, Some(at(50 * day).block.time));
+
+    // Only the beneficiary can claim
+    let err = execute(
+        deps.as_mut(),
+        at(50 * day),
+        mock_info("grandma", &[]),
+        claim.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    execute(deps.as_mut(), at(50 * day), mock_info("heir", &[]), claim).unwrap();
+    let owner: OwnerOfResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::OwnerOf {
+                token_id: "1".into(),
+                include_expired: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(owner.owner, "heir");
+
+    // The balance comes with the NFT
+    let balance: Coin = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Balance {
+                    token_id: "1".into(),
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(balance.amount, Uint128::new(500));
+}
+
+#[test]
+fn emergency_pause() {
+    let mut deps = setup_with_token(default_instantiate_msg(), "alice");
+    let week = 7 * 24 * 60 * 60;
+    let at = |seconds: u64| {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(seconds);
+        env
+    };
+    let pause = |withdraw: bool| ExecuteMsg::Extension {
+        msg: ExecuteExt::UpdatePause {
+            mint: false,
+            deposit: true,
+            withdraw,
+            transfer: false,
+        },
+    };
+    let deposit = ExecuteMsg::Extension {
+        msg: ExecuteExt::Deposit {
+            token_id: "1".into(),
+        },
+    };
+    let burn = ExecuteMsg::Burn {
+        token_id: "1".into(),
+    };
+
+    // Only the minter can pause
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        pause(true),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        pause(true),
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(500, "ujuno")),
+        deposit.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Paused {});
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        burn.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Paused {});
+    for msg in [ExecuteExt::ClaimRebate {}, ExecuteExt::ReleaseProceeds {}] {
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::Extension { msg },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+    }
+
+    // Other actions carry on
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::TransferNft {
+            recipient: "carol".into(),
+            token_id: "1".into(),
+        },
+    )
+    .unwrap();
+
+    // Pausing again doesn't extend the withdrawal pause
+    execute(
+        deps.as_mut(),
+        at(week - 1),
+        mock_info("bob", &[]),
+        pause(true),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        at(week - 1),
+        mock_info("carol", &[]),
+        burn.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Paused {});
+
+    // Withdrawals resume on their own, and can't be paused again straight away
+    let err = execute(deps.as_mut(), at(week), mock_info("bob", &[]), pause(true)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WithdrawPauseCooldown {
+            until: at(2 * week).block.time
+        }
+    );
+    let err = execute(
+        deps.as_mut(),
+        at(week),
+        mock_info("carol", &coins(500, "ujuno")),
+        deposit,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Paused {});
+    execute(deps.as_mut(), at(week), mock_info("carol", &[]), burn).unwrap();
+}
+
+#[test]
+fn ownership_transfer() {
+    let mut deps = setup_with_token(default_instantiate_msg(), "alice");
+    let update_fee = ExecuteMsg::Extension {
+        msg: ExecuteExt::UpdateFee { fee: None },
+    };
+
+    // The new owner has to accept before the offer expires
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        ExecuteMsg::UpdateOwnership(Action::TransferOwnership {
+            new_owner: "dao".into(),
+            expiry: Some(Expiration::AtHeight(mock_env().block.height + 10)),
+        }),
+    )
+    .unwrap();
+    let ownership: Ownership<Addr> =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Ownership {}).unwrap()).unwrap();
+    assert_eq!(ownership.owner, Some(Addr::unchecked("bob")));
+    assert_eq!(ownership.pending_owner, Some(Addr::unchecked("dao")));
+
+    let mut env = mock_env();
+    env.block.height += 10;
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("dao", &[]),
+        ExecuteMsg::UpdateOwnership(Action::AcceptOwnership),
+    )
+    .unwrap_err();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("dao", &[]),
+        ExecuteMsg::UpdateOwnership(Action::AcceptOwnership),
+    )
+    .unwrap();
+
+    // Admin actions move with ownership
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        update_fee.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("dao", &[]),
+        update_fee.clone(),
+    )
+    .unwrap();
+
+    // Once renounced, nobody can administer the contract
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("dao", &[]),
+        ExecuteMsg::UpdateOwnership(Action::RenounceOwnership),
+    )
+    .unwrap();
+    let err = execute(deps.as_mut(), mock_env(), mock_info("dao", &[]), update_fee).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
+
+#[test]
+fn roles() {
+    let mut deps = setup_with_token(default_instantiate_msg(), "alice");
+    let update_uri = ExecuteMsg::Extension {
+        msg: ExecuteExt::UpdateTokenUri {
+            token_id: "1".into(),
+            token_uri: "ipfs://new".into(),
+        },
+    };
+    let grant = ExecuteMsg::Extension {
+        msg: ExecuteExt::GrantRole {
+            address: "designer".into(),
+            role: Role::MetadataUpdater,
+        },
+    };
+
+    // Only the owner can grant roles
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("designer", &[]),
+        grant.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("designer", &[]),
+        update_uri.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), grant).unwrap();
+    let res: RolesResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Roles {
+                    address: "designer".into(),
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.roles, vec![Role::MetadataUpdater]);
+
+    // A role only covers its own duties
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("designer", &[]),
+        update_uri.clone(),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("designer", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::UpdateFee { fee: None },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::RevokeRole {
+                address: "designer".into(),
+                role: Role::MetadataUpdater,
+            },
+        },
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("designer", &[]),
+        update_uri,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
+
+#[test]
+fn solvency_and_sweep() {
+    let mut deps = setup_with_token(default_instantiate_msg(), "alice");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(500, "ujuno")),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::Deposit {
+                token_id: "1".into(),
+            },
+        },
+    )
+    .unwrap();
+
+    // Someone sends funds to the contract by accident
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, coins(700, "ujuno"));
+    let res: SolvencyResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Solvency {},
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.denoms,
+        vec![DenomSolvency {
+            denom: "ujuno".into(),
+            owed: Uint128::new(500),
+            balance: Uint128::new(700),
+            surplus: Uint128::new(200),
+            shortfall: Uint128::zero(),
+        }]
+    );
+
+    // Only the surplus can be swept
+    let sweep = |amount: Option<u128>| ExecuteMsg::Extension {
+        msg: ExecuteExt::Sweep {
+            denom: "ujuno".into(),
+            amount: amount.map(Uint128::new),
+            recipient: "treasury".into(),
+        },
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        sweep(Some(201)),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientSurplus {
+            surplus: Uint128::new(200)
+        }
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        sweep(None),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "treasury".into(),
+            amount: coins(200, "ujuno"),
+        })
+    );
+
+    // Breaking open pays out what is owed
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Burn {
+            token_id: "1".into(),
+        },
+    )
+    .unwrap();
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, vec![]);
+    let res: SolvencyResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Solvency {},
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.denoms[0].owed, Uint128::zero());
+    assert_eq!(res.denoms[0].shortfall, Uint128::zero());
+}
+
+#[test]
+fn credit_unassigned() {
+    let mut deps = setup_with_token(default_instantiate_msg(), "alice");
+    let credit = |amount: u128| ExecuteMsg::Extension {
+        msg: ExecuteExt::CreditUnassigned {
+            token_id: "1".into(),
+            amount: Uint128::new(amount),
+        },
+    };
+
+    // Alice sent funds straight to the contract
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, coins(300, "ujuno"));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        credit(300),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        credit(301),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientSurplus {
+            surplus: Uint128::new(300)
+        }
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        credit(300),
+    )
+    .unwrap();
+
+    let balance: Coin = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Balance {
+                    token_id: "1".into(),
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(balance.amount, Uint128::new(300));
+
+    // The same funds can't be credited twice
+    let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), credit(1)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientSurplus {
+            surplus: Uint128::zero()
+        }
+    );
+}
+
+#[test]
+fn min_raise() {
+    let msg = InstantiateMsg {
+        min_raise: Some(MinRaise {
+            min_tokens: 2,
+            deadline: mock_env().block.time.plus_seconds(86400),
+        }),
+        ..default_instantiate_msg()
+    };
+    let mut after_deadline = mock_env();
+    after_deadline.block.time = after_deadline.block.time.plus_seconds(86400);
+    let mint = ExecuteMsg::Mint {
+        token_id: "2".into(),
+        owner: "carol".into(),
+        token_uri: None,
+        extension: MetadataExt {},
+        guardian: None,
+    };
+    let raise = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, env: Env| {
+        from_binary::<RaiseResponse>(
+            &query(
+                deps.as_ref(),
+                env,
+                QueryMsg::Extension {
+                    msg: QueryExt::Raise {},
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    // The raise fails if not enough NFTs sell by the deadline
+    let mut deps = setup_with_token(msg.clone(), "alice");
+    let res = raise(&deps, mock_env());
+    assert_eq!(res.status, Some(RaiseStatus::Pending));
+    assert_eq!(res.minted, 1);
+    assert_eq!(res.escrowed, coin(1000000, "ujuno"));
+
+    // The mint payment is only refunded if the raise fails
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Burn {
+            token_id: "1".into(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MintRefundable {});
+
+    let err = execute(
+        deps.as_mut(),
+        after_deadline.clone(),
+        mock_info("carol", &coins(1000000, "ujuno")),
+        mint.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::RaiseFailed {});
+    let err = execute(
+        deps.as_mut(),
+        after_deadline.clone(),
+        mock_info("carol", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::ReleaseProceeds {},
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::RaiseNotSucceeded {});
+
+    // Holders get the mint price back when they burn
+    let simulation: SimulateBurnResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            after_deadline.clone(),
+            QueryMsg::Extension {
+                msg: QueryExt::SimulateBurn {
+                    token_id: "1".into(),
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(simulation.mint_refund, Some(coin(1000000, "ujuno")));
+    let res = execute(
+        deps.as_mut(),
+        after_deadline.clone(),
+        mock_info("alice", &[]),
+        ExecuteMsg::Burn {
+            token_id: "1".into(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".into(),
+            amount: coins(1000000, "ujuno"),
+        })
+    );
+    assert_eq!(
+        raise(&deps, after_deadline.clone()).escrowed.amount,
+        Uint128::zero()
+    );
+
+    // Once enough NFTs sell, proceeds are released and burning doesn't refund
+    let mut deps = setup_with_token(msg, "alice");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("carol", &coins(1000000, "ujuno")),
+        mint,
+    )
+    .unwrap();
+    assert_eq!(
+        raise(&deps, mock_env()).status,
+        Some(RaiseStatus::Succeeded)
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        after_deadline.clone(),
+        mock_info("carol", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::ReleaseProceeds {},
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "larry".into(),
+            amount: coins(2000000, "ujuno"),
+        })
+    );
+    let res = execute(
+        deps.as_mut(),
+        after_deadline,
+        mock_info("alice", &[]),
+        ExecuteMsg::Burn {
+            token_id: "1".into(),
+        },
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+}
+
+#[test]
+fn dutch_auction() {
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        InstantiateMsg {
+            max_nft_supply: Some(3),
+            dutch_auction: Some(DutchAuctionMsg {
+                start_price: Uint128::new(1000),
+                floor_price: Uint128::new(400),
+                decay: Uint128::new(100),
+                decay_interval: Duration::Time(10),
+                rebate: true,
+            }),
+            ..default_instantiate_msg()
+        },
+    )
+    .unwrap();
+    let at = |seconds: u64| {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(seconds);
+        env
+    };
+    let mint = |token_id: &str| ExecuteMsg::Mint {
+        token_id: token_id.into(),
+        owner: "".into(),
+        token_uri: None,
+        extension: MetadataExt {},
+        guardian: None,
+    };
+    let claim = ExecuteMsg::Extension {
+        msg: ExecuteExt::ClaimRebate {},
+    };
+
+    // Overpayments are refunded, and what's above the floor is held for rebates
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(1200, "ujuno")),
+        mint("1"),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages
+            .into_iter()
+            .map(|msg| msg.msg)
+            .collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alice".into(),
+                amount: coins(200, "ujuno"),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "larry".into(),
+                amount: coins(400, "ujuno"),
+            }),
+        ]
+    );
+
+    let price: Coin = from_binary(
+        &query(
+            deps.as_ref(),
+            at(25),
+            QueryMsg::Extension {
+                msg: QueryExt::CurrentPrice {},
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(price, coin(800, "ujuno"));
+
+    let err = execute(
+        deps.as_mut(),
+        at(25),
+        mock_info("carol", &coins(799, "ujuno")),
+        mint("2"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongAmount {});
+    execute(
+        deps.as_mut(),
+        at(25),
+        mock_info("carol", &coins(800, "ujuno")),
+        mint("2"),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        at(25),
+        mock_info("alice", &[]),
+        claim.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AuctionNotSettled {});
+
+    // Selling out settles the auction at the last price
+    execute(
+        deps.as_mut(),
+        at(30),
+        mock_info("dave", &coins(700, "ujuno")),
+        mint("3"),
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        at(30),
+        mock_info("alice", &[]),
+        claim.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".into(),
+            amount: coins(300, "ujuno"),
+        })
+    );
+    let err = execute(
+        deps.as_mut(),
+        at(30),
+        mock_info("alice", &[]),
+        claim.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoRebate {});
+    let res = execute(deps.as_mut(), at(30), mock_info("carol", &[]), claim).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "carol".into(),
+            amount: coins(100, "ujuno"),
+        })
+    );
+
+    // The recipient gets the clearing price above the floor for every sale
+    let res = execute(
+        deps.as_mut(),
+        at(30),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Extension {
+            msg: ExecuteExt::ReleaseProceeds {},
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "larry".into(),
+            amount: coins(900, "ujuno"),
+        })
+    );
+}
+
+#[test]
+fn bonding_curve() {
+    let price_at = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, token_number: u64| {
+        from_binary::<Coin>(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Extension {
+                    msg: QueryExt::PriceAt { token_number },
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    let mint = ExecuteMsg::Mint {
+        token_id: "".into(),
+        owner: "".into(),
+        token_uri: None,
+        extension: MetadataExt {},
+        guardian: None,
+    };
+
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        InstantiateMsg {
+            bonding_curve: Some(BondingCurve::Linear {
+                base_price: Uint128::new(100),
+                increment: Uint128::new(50),
+            }),
+            ..default_instantiate_msg()
+        },
+    )
+    .unwrap();
+    assert_eq!(price_at(&deps, 1), coin(100, "ujuno"));
+    assert_eq!(price_at(&deps, 3), coin(200, "ujuno"));
+
+    // Overpayments are refunded, and the price rises with every mint
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(120, "ujuno")),
+        mint.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".into(),
+            amount: coins(20, "ujuno"),
+        })
+    );
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("carol", &coins(100, "ujuno")),
+        mint.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongAmount {});
+    let price: Coin = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::CurrentPrice {},
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(price, coin(150, "ujuno"));
+
+    // Exponential curves compound the growth
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        InstantiateMsg {
+            bonding_curve: Some(BondingCurve::Exponential {
+                base_price: Uint128::new(1000),
+                growth_bps: 1000,
+            }),
+            ..default_instantiate_msg()
+        },
+    )
+    .unwrap();
+    assert_eq!(price_at(&deps, 1), coin(1000, "ujuno"));
+    assert_eq!(price_at(&deps, 3), coin(1210, "ujuno"));
+}
+
+#[test]
+fn payment_options() {
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        InstantiateMsg {
+            payment_options: vec![
+                PaymentOptionMsg::Native {
+                    denom: "ibc/usdc".into(),
+                    amount: Uint128::new(5000000),
+                },
+                PaymentOptionMsg::Cw20 {
+                    address: "community".into(),
+                    amount: Uint128::new(100),
+                },
+            ],
+            ..default_instantiate_msg()
+        },
+    )
+    .unwrap();
+    let mint = ExecuteMsg::Mint {
+        token_id: "".into(),
+        owner: "".into(),
+        token_uri: None,
+        extension: MetadataExt {},
+        guardian: None,
+    };
+
+    let res: PaymentOptionsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::PaymentOptions {},
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.options.len(), 3);
+
+    // Native payment options go to the recipient in the denom paid
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(4000000, "ibc/usdc")),
+        mint.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongAmount {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(5000000, "uatom")),
+        mint.clone(),
+    )
+    .unwrap_err();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(5000000, "ibc/usdc")),
+        mint,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "larry".into(),
+            amount: coins(5000000, "ibc/usdc"),
+        })
+    );
+
+    // cw20 payments come through Receive from an accepted token
+    let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "carol".into(),
+        amount: Uint128::new(100),
+        msg: to_binary(&ReceiveMsg::Mint { guardian: None }).unwrap(),
+    });
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("fake", &[]),
+        receive.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("community", &[]),
+        receive,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "community".into(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "larry".into(),
+                amount: Uint128::new(100),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    let owner: OwnerOfResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::OwnerOf {
+                token_id: "2".into(),
+                include_expired: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(owner.owner, "carol");
+}
+
+#[test]
+fn mint_seed() {
+    let mut deps = setup_with_token(
+        InstantiateMsg {
+            mint_price: coin(4000000, "ujuno"),
+            mint_seed_bps: Some(5000),
+            ..default_instantiate_msg()
+        },
+        "alice",
+    );
+
+    // Half the mint price is in the new piggy bank, the rest goes to the recipient
+    let balance: Coin = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Extension {
+                msg: QueryExt::Balance {
+                    token_id: "1".into(),
+                },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(balance, coin(2000000, "ujuno"));
+    let info: AllNftInfoResponse<MetadataExt> = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AllNftInfo {
+                token_id: "1".into(),
+                include_expired: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(info.info.token_uri.unwrap().ends_with("sapling.json"));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("carol", &coins(4000000, "ujuno")),
+        ExecuteMsg::Mint {
+            token_id: "".into(),
+            owner: "".into(),
+            token_uri: None,
+            extension: MetadataExt {},
+            guardian: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "larry".into(),
+            amount: coins(2000000, "ujuno"),
+        })
+    );
+
+    // The mint price has to be depositable
+    let err = instantiate(
+        mock_dependencies().as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        InstantiateMsg {
+            mint_price: coin(4000000, "uatom"),
+            mint_seed_bps: Some(5000),
+            ..default_instantiate_msg()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidMintSeed {});
+
+    // Every NFT has to be seeded, and seeds can't be refunded
+    let err = instantiate(
+        mock_dependencies().as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        InstantiateMsg {
+            mint_seed_bps: Some(5000),
+            payment_options: vec![PaymentOptionMsg::Native {
+                denom: "uatom".into(),
+                amount: Uint128::new(100),
+            }],
+            ..default_instantiate_msg()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidPaymentOptions {});
+    let err = instantiate(
+        mock_dependencies().as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        InstantiateMsg {
+            mint_seed_bps: Some(5000),
+            min_raise: Some(MinRaise {
+                min_tokens: 2,
+                deadline: mock_env().block.time.plus_seconds(86400),
+            }),
+            ..default_instantiate_msg()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidMintSeed {});
+}
+
+#[test]
+fn purchase_batch() {
+    let mut deps = setup_with_token(
+        InstantiateMsg {
+            max_nft_supply: Some(5),
+            max_mints_per_wallet: Some(3),
+            ..default_instantiate_msg()
+        },
+        "alice",
+    );
+    let batch = |count| ExecuteMsg::Extension {
+        msg: ExecuteExt::PurchaseBatch {
+            count,
+            recipient: Some("dave".into()),
+            guardian: None,
+        },
+    };
+
+    // Has to pay for every NFT in the batch
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("carol", &coins(2000000, "ujuno")),
+        batch(3),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongAmount {});
+
+    // Can't buy past the per-wallet cap
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("carol", &coins(4000000, "ujuno")),
+        batch(4),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WalletLimitReached { max: 3 });
+
+    // Mints sequential NFTs to the recipient with one payout
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("carol", &coins(3000000, "ujuno")),
+        batch(3),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages
+            .iter()
+            .map(|msg| msg.msg.clone())
+            .collect::<Vec<_>>(),
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            to_address: "larry".into(),
+            amount: coins(3000000, "ujuno"),
+        })]
+    );
+    for token_id in ["2", "3", "4"] {
+        let owner: OwnerOfResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::OwnerOf {
+                    token_id: token_id.into(),
+                    include_expired: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(owner.owner, "dave");
+    }
+
+    // Single mints count against the cap too
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("carol", &coins(1000000, "ujuno")),
+        ExecuteMsg::Mint {
+            token_id: "".into(),
+            owner: "".into(),
+            token_uri: None,
+            extension: MetadataExt {},
+            guardian: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WalletLimitReached { max: 3 });
+
+    // Can't buy past the max NFT supply
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("erin", &coins(2000000, "ujuno")),
+        batch(2),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MintedOut {});
+}